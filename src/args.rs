@@ -3,51 +3,163 @@ use clap::{Args, Parser, Subcommand};
 #[derive(Parser, Debug)]
 #[clap(author, about, long_about = None)]
 pub struct CommandArguments {
+  /// Path to the config file, by default .ecs_helpers.toml is searched from the current directory upwards
+  #[clap(long, env = "ECS_HELPERS_CONFIG")]
+  pub config_file: Option<std::path::PathBuf>,
+
   /// Use image tag env prefix
-  #[clap(long, env, default_value = "false")]
-  pub use_image_tag_env_prefix: bool,
+  #[clap(long, env, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+  pub use_image_tag_env_prefix: Option<bool>,
 
   /// Set environment, it there is empty will try to detect based on the branch name
   #[clap(short, long, env)]
   pub environment: Option<String>,
 
+  /// Map branch to environment as pattern=environment, pattern is a glob or a regex prefixed with regex:, e.g. review/*=review-$1, rules are separated by ;
+  #[clap(long, env = "BRANCH_ENVIRONMENTS", value_delimiter = ';')]
+  pub branch_environment: Option<Vec<String>>,
+
   /// Set version which will be applied to all containers in the task if tag is present in the repo
   #[clap(short, long, env)]
   pub version: Option<String>,
 
+  /// Build the version from a template of {environment}, {sha}, {short_sha} and {semver}, e.g. {environment}-{short_sha}
+  #[clap(long, env)]
+  pub version_template: Option<String>,
+
   /// Set project name, will be used to detect cluster
   #[clap(short, long, env)]
-  pub project: String,
+  pub project: Option<String>,
 
   /// Set application name, will be used to detect service and task definition
   #[clap(short, long, env)]
-  pub application: String,
+  pub application: Option<String>,
 
   /// The AWS account ID
   #[clap(long, env)]
   pub aws_account_id: Option<String>,
 
+  /// Use the named AWS profile from the shared config and credentials files
+  #[clap(long, env = "AWS_PROFILE")]
+  pub profile: Option<String>,
+
+  /// Set AWS region, by default it is resolved from the environment or the AWS profile
+  #[clap(long, env = "AWS_REGION")]
+  pub region: Option<String>,
+
+  #[clap(flatten)]
+  pub assume_role: AssumeRoleArguments,
+
   #[clap(subcommand)]
   pub cmd: Commands,
 }
 
+#[derive(Args, Debug)]
+pub struct AssumeRoleArguments {
+  /// Assume the IAM role before calling AWS, e.g. to deploy to another account
+  #[clap(long, env = "ASSUME_ROLE_ARN")]
+  pub role_arn: Option<String>,
+
+  /// Set external ID required by the trust policy of the role
+  #[clap(long, env = "ASSUME_ROLE_EXTERNAL_ID", requires = "role_arn")]
+  pub external_id: Option<String>,
+
+  /// Set session name of the assumed role [default: ecs-helpers]
+  #[clap(long, env = "ASSUME_ROLE_SESSION_NAME", requires = "role_arn")]
+  pub role_session_name: Option<String>,
+
+  /// Set duration of the assumed role session in seconds [default: 3600]
+  #[clap(long, env = "ASSUME_ROLE_DURATION", requires = "role_arn")]
+  pub duration: Option<u64>,
+}
+
 #[derive(Args, Debug)]
 pub struct DeployCommandArguments {
-  /// Set timeout in seconds how long to wait until deployment finished
-  #[clap(short, long, env, default_value = "600")]
-  pub timeout: u64,
+  /// Set timeout in seconds how long to wait until deployment finished [default: 600]
+  #[clap(short, long, env)]
+  pub timeout: Option<u64>,
 
   /// Set cluster name, could be auto-detected if project and environment are specified
   #[clap(long, env)]
   pub cluster: Option<String>,
 
-  /// Set service, could be auto-detected if application and environment are specified
-  #[clap(short, long, env)]
-  pub service: Option<String>,
+  /// Set service, could be auto-detected if application and environment are specified.
+  /// Pass several times or comma separated to deploy multiple services at once
+  #[clap(short, long, env, value_delimiter = ',')]
+  pub service: Option<Vec<String>>,
+
+  /// Deploy all services of the cluster with a name matching the pattern, pattern is a glob or a regex prefixed with regex:, e.g. shop-*-production
+  #[clap(long, env, conflicts_with = "service")]
+  pub service_pattern: Option<String>,
+
+  /// Roll back to the previous task definition if the deployment fails or times out
+  #[clap(long, env, default_value = "false")]
+  pub rollback: bool,
+
+  /// Print the task definition changes without registering it and updating the service
+  #[clap(long, env, default_value = "false")]
+  pub dry_run: bool,
+
+  /// Override image of a container as container=tag or container=image, takes precedence over version.
+  /// Pass several times or comma separated
+  #[clap(long, env = "IMAGE_OVERRIDES", value_delimiter = ',')]
+  pub image_override: Option<Vec<String>>,
+
+  /// Pin images stored in ECR to their digest instead of the mutable tag
+  #[clap(long, env, default_value = "false")]
+  pub pin_digest: bool,
+
+  #[clap(flatten)]
+  pub image_scan: ImageScanArguments,
 }
 
 #[derive(Args, Debug)]
-pub struct LoginCommandArguments {}
+pub struct LoginCommandArguments {
+  /// Write ECR credentials to the docker config file instead of running docker login
+  #[clap(long, env, default_value = "false")]
+  pub write_docker_config: bool,
+
+  /// Set path to the docker config file, implies --write-docker-config [default: $DOCKER_CONFIG/config.json or ~/.docker/config.json]
+  #[clap(long, env)]
+  pub docker_config_file: Option<std::path::PathBuf>,
+
+  /// Log in to additional ECR registries as account_id, account_id:region or the registry host, separated by commas
+  #[clap(long, env = "ECR_REGISTRIES", value_delimiter = ',')]
+  pub registry: Option<Vec<String>>,
+
+  /// Log in to ECR Public (public.ecr.aws) as well
+  #[clap(long, env = "ECR_PUBLIC", default_value = "false")]
+  pub public: bool,
+
+  /// Container engine used to log in, build and push [default: the first of docker, podman, nerdctl or buildah found on PATH]
+  #[clap(long, env = "CONTAINER_ENGINE", value_enum, ignore_case = true)]
+  pub engine: Option<ContainerEngine>,
+}
+
+/// Container engine CLI, all of them log in, build, pull and push the same way.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerEngine {
+  Docker,
+  Podman,
+  Nerdctl,
+  Buildah,
+}
+
+/// Action of the docker credential helper protocol.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CredentialHelperAction {
+  Get,
+  Store,
+  Erase,
+  List,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CredentialHelperCommandArguments {
+  /// Action requested by docker, the registry or credentials are read from stdin
+  #[clap(value_enum)]
+  pub action: CredentialHelperAction,
+}
 
 #[derive(Args, Debug)]
 pub struct ExportImagesArguments {}
@@ -58,9 +170,9 @@ pub struct RunCommandArguments {
   #[clap(short, long, env)]
   pub command: String,
 
-  /// Set timeout in seconds how long to wait until deployment finished
-  #[clap(short, long, env, default_value = "600")]
-  pub timeout: u64,
+  /// Set timeout in seconds how long to wait until deployment finished [default: 600]
+  #[clap(short, long, env)]
+  pub timeout: Option<u64>,
 
   /// Set cluster name, could be auto-detected if project and environment are specified
   #[clap(long, env)]
@@ -77,6 +189,15 @@ pub struct RunCommandArguments {
   /// Set container name (default is the first container in the task definition)
   #[clap(long, env, alias = "container-name")]
   pub container: Option<String>,
+
+  /// Override image of a container as container=tag or container=image, takes precedence over version.
+  /// Pass several times or comma separated
+  #[clap(long, env = "IMAGE_OVERRIDES", value_delimiter = ',')]
+  pub image_override: Option<Vec<String>>,
+
+  /// Pin images stored in ECR to their digest instead of the mutable tag
+  #[clap(long, env, default_value = "false")]
+  pub pin_digest: bool,
 }
 
 #[derive(Args, Debug)]
@@ -102,6 +223,33 @@ pub struct ExecCommandArguments {
   pub container: Option<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct LogsCommandArguments {
+  /// Set cluster name, could be auto-detected if project and environment are specified
+  #[clap(long, env)]
+  pub cluster: Option<String>,
+
+  /// Set service, could be auto-detected if application and environment are specified
+  #[clap(short, long, env)]
+  pub service: Option<String>,
+
+  /// Show logs only of the container (default is all containers in the task definition)
+  #[clap(long, env, alias = "container-name")]
+  pub container: Option<String>,
+
+  /// Show logs newer than a relative duration like 30s, 10m, 2h or 1d
+  #[clap(long, env, default_value = "10m")]
+  pub since: String,
+
+  /// Keep polling for new log events
+  #[clap(short, long, env, default_value = "false")]
+  pub follow: bool,
+
+  /// CloudWatch Logs filter pattern, e.g. ERROR or "{ $.level = \"error\" }"
+  #[clap(long, env)]
+  pub filter_pattern: Option<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct ExportEnvSecretsCommandArguments {
   /// Env variables to export
@@ -140,13 +288,109 @@ pub struct BuildAndPushCommandArguments {
   #[clap(long, env)]
   pub repository: Option<String>,
 
+  /// Push to an ECR Public (public.ecr.aws) repository instead of a private one
+  #[clap(
+    long,
+    env = "ECR_PUBLIC",
+    default_value = "false",
+    conflicts_with_all = ["scan_severity", "retag_existing"]
+  )]
+  pub public: bool,
+
   /// Set the build target for the docker image
   #[clap(long, env)]
   pub target: Option<String>,
 
-  /// Set platform for docker image
-  #[clap(long, env, default_value = "linux/amd64")]
-  pub platform: String,
+  /// Set platform for docker image, several comma separated platforms are published as a single manifest list
+  #[clap(long, env, value_delimiter = ',', default_value = "linux/amd64")]
+  pub platform: Vec<String>,
+
+  /// Build with classic docker build or with buildx, which pushes the image itself
+  #[clap(
+    long,
+    env = "BUILD_BACKEND",
+    value_enum,
+    ignore_case = true,
+    default_value = "docker"
+  )]
+  pub backend: BuildBackend,
+
+  /// Where buildx stores the build cache when --cache is set
+  #[clap(long, env, value_enum, ignore_case = true, default_value = "registry")]
+  pub cache_mode: CacheMode,
+
+  /// Set tag of the registry build cache in the ECR repository
+  #[clap(long, env, default_value = "buildcache")]
+  pub cache_tag: String,
+
+  /// Push additional tags rendered from templates, e.g. {branch}-{date},{semver}, the version tag is always pushed
+  #[clap(long, env = "IMAGE_TAGS", value_delimiter = ',')]
+  pub tag: Option<Vec<String>>,
+
+  /// Template of the moving tag pointing to the last build, e.g. {environment}-latest
+  #[clap(long, env, default_value = "latest")]
+  pub latest_tag: String,
+
+  /// Don't push the moving latest tag
+  #[clap(long, env, default_value = "false", conflicts_with = "latest_tag")]
+  pub no_latest: bool,
+
+  /// Build and push even if the version tag already exists in ECR
+  #[clap(long, env = "FORCE_BUILD", default_value = "false")]
+  pub force: bool,
+
+  /// When the version tag already exists, add the other tags to the existing image instead of skipping them
+  #[clap(long, env, default_value = "false", conflicts_with = "force")]
+  pub retag_existing: bool,
+
+  /// Container engine used to log in, build and push [default: the first of docker, podman, nerdctl or buildah found on PATH]
+  #[clap(long, env = "CONTAINER_ENGINE", value_enum, ignore_case = true)]
+  pub engine: Option<ContainerEngine>,
+
+  #[clap(flatten)]
+  pub image_scan: ImageScanArguments,
+}
+
+/// Tool used to build the image.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildBackend {
+  Docker,
+  Buildx,
+}
+
+/// Build cache storage of buildx.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheMode {
+  /// Separate cache image in the repository, keeps layers of all build stages
+  Registry,
+  /// Cache metadata embedded into the pushed image, keeps layers of the final stage only
+  Inline,
+}
+
+/// Severity of the ECR image scan findings, from the most severe.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScanSeverity {
+  Critical,
+  High,
+  Medium,
+  Low,
+  Informational,
+  Undefined,
+}
+
+#[derive(Args, Debug)]
+pub struct ImageScanArguments {
+  /// Wait for the ECR image scan and fail if there are findings of this or a higher severity
+  #[clap(long, env, value_enum, ignore_case = true)]
+  pub scan_severity: Option<ScanSeverity>,
+
+  /// Set how many findings of the scan severity or higher are allowed
+  #[clap(long, env, default_value = "0")]
+  pub scan_max_findings: i32,
+
+  /// Set timeout in seconds how long to wait for the image scan results
+  #[clap(long, env, default_value = "600")]
+  pub scan_timeout: u64,
 }
 
 #[derive(Subcommand, Debug)]
@@ -155,6 +399,10 @@ pub enum Commands {
   #[clap(alias = "ecr_login")]
   Login(LoginCommandArguments),
 
+  /// Docker credential helper for ECR, use it as credHelpers through a docker-credential-ecs-helpers script
+  #[clap(alias = "credential_helper")]
+  CredentialHelper(CredentialHelperCommandArguments),
+
   /// Prints images for the project and application
   #[clap(alias = "export_images")]
   ExportImages(ExportImagesArguments),
@@ -167,6 +415,9 @@ pub enum Commands {
   #[clap(alias = "exec_command")]
   Exec(ExecCommandArguments),
 
+  /// Tail CloudWatch logs of all running tasks of the service
+  Logs(LogsCommandArguments),
+
   /// Export environment variables from AWS SSM Parameter Store
   #[clap(alias = "export_env_secrets")]
   ExportEnvSecrets(ExportEnvSecretsCommandArguments),
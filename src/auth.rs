@@ -1,23 +1,35 @@
-use std::process::{Output, Stdio};
+use std::{
+  collections::BTreeMap,
+  path::Path,
+  process::{Output, Stdio},
+};
 
 use aws_config::{Region, SdkConfig};
-use tokio::{io::AsyncWriteExt, process::Command};
+use futures::future::try_join_all;
+use tokio::io::AsyncWriteExt;
 
-use crate::{ecr::EcrClient, errors::EcsHelperVarietyError};
+use crate::{
+  args::ContainerEngine,
+  container_engine, docker_config,
+  ecr::{EcrClient, EcrRegistry, RegistryAuthorization},
+  ecr_public::EcrPublicClient,
+  errors::EcsHelperVarietyError,
+};
 
-async fn run_docker_login(
-  account_id: &String,
-  region: &Region,
-  token: &String,
+async fn run_login(
+  engine: ContainerEngine,
+  registry: &str,
+  username: &str,
+  token: &str,
 ) -> miette::Result<Output, EcsHelperVarietyError> {
-  let mut child = Command::new("docker")
+  let mut child = container_engine::command(engine)
     .stdin(Stdio::piped())
     .stdout(Stdio::piped())
     .arg("login")
     .arg("-u")
-    .arg("AWS")
+    .arg(username)
     .arg("--password-stdin")
-    .arg(format!("{account_id}.dkr.ecr.{region}.amazonaws.com"))
+    .arg(registry)
     .spawn()
     .map_err(EcsHelperVarietyError::ReedOutputError)?;
 
@@ -35,6 +47,7 @@ async fn run_docker_login(
 }
 
 pub async fn login_to_ecr(
+  engine: ContainerEngine,
   sdk_config: &SdkConfig,
   region: &Region,
   account_id: &String,
@@ -42,5 +55,153 @@ pub async fn login_to_ecr(
   let ecr_client = EcrClient::new(sdk_config);
   let token = ecr_client.get_token().await?;
 
-  run_docker_login(account_id, region, &token).await
+  run_login(
+    engine,
+    &format!("{account_id}.dkr.ecr.{region}.amazonaws.com"),
+    "AWS",
+    &token,
+  )
+  .await
+}
+
+/// Requests credentials of the registries, one request is sent per region.
+pub async fn get_registry_authorizations(
+  sdk_config: &SdkConfig,
+  registries: &[EcrRegistry],
+) -> miette::Result<Vec<RegistryAuthorization>, EcsHelperVarietyError> {
+  let mut registry_ids_by_region: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+
+  for registry in registries {
+    let registry_ids = registry_ids_by_region
+      .entry(registry.region.to_owned())
+      .or_default();
+
+    if !registry_ids.contains(&registry.account_id) {
+      registry_ids.push(registry.account_id.to_owned());
+    }
+  }
+
+  let authorizations = try_join_all(registry_ids_by_region.iter().map(
+    |(region, registry_ids)| async move {
+      EcrClient::for_region(sdk_config, region.as_ref())
+        .get_authorizations(registry_ids)
+        .await
+    },
+  ))
+  .await?;
+
+  Ok(authorizations.into_iter().flatten().collect())
+}
+
+async fn login(
+  engine: ContainerEngine,
+  authorization: RegistryAuthorization,
+) -> miette::Result<String, EcsHelperVarietyError> {
+  let output = run_login(
+    engine,
+    &authorization.registry,
+    &authorization.username,
+    &authorization.password,
+  )
+  .await?;
+
+  if !output.status.success() {
+    return Err(EcsHelperVarietyError::LoginFailed(format!(
+      "Login to {} failed with status code: {}",
+      authorization.registry,
+      output.status.code().unwrap_or(0)
+    )));
+  }
+
+  log::info!("Logged in to {}", authorization.registry);
+
+  Ok(authorization.registry)
+}
+
+/// Logs the container engine in to every registry, returns the logged in registry hosts.
+pub async fn login_to_registries(
+  engine: ContainerEngine,
+  sdk_config: &SdkConfig,
+  registries: &[EcrRegistry],
+) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
+  let authorizations = get_registry_authorizations(sdk_config, registries).await?;
+
+  try_join_all(
+    authorizations
+      .into_iter()
+      .map(|authorization| login(engine, authorization)),
+  )
+  .await
+}
+
+/// Logs the container engine in to `public.ecr.aws`.
+pub async fn login_to_public_ecr(
+  engine: ContainerEngine,
+  sdk_config: &SdkConfig,
+) -> miette::Result<String, EcsHelperVarietyError> {
+  let authorization = EcrPublicClient::new(sdk_config).get_authorization().await?;
+
+  login(engine, authorization).await
+}
+
+fn store_docker_config(
+  authorizations: &[RegistryAuthorization],
+  config_file: Option<&Path>,
+) -> miette::Result<(), EcsHelperVarietyError> {
+  let config_path = docker_config::config_path(config_file)?;
+
+  for authorization in authorizations {
+    docker_config::store_auth(
+      &config_path,
+      &authorization.registry,
+      &authorization.username,
+      &authorization.password,
+    )?;
+
+    log::info!(
+      "Credentials for {} were written to {}",
+      authorization.registry,
+      config_path.display()
+    );
+  }
+
+  Ok(())
+}
+
+/// Writes ECR credentials directly to the docker config file, so tools which only read it
+/// (buildah, podman, kaniko) could pull and push without docker CLI.
+///
+/// The default registry of the account is used when registries are empty.
+/// Returns the registries the credentials were stored for.
+pub async fn write_ecr_docker_config(
+  sdk_config: &SdkConfig,
+  registries: &[EcrRegistry],
+  config_file: Option<&Path>,
+) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
+  let authorizations = if registries.is_empty() {
+    vec![EcrClient::new(sdk_config).get_authorization().await?]
+  } else {
+    get_registry_authorizations(sdk_config, registries).await?
+  };
+
+  store_docker_config(&authorizations, config_file)?;
+
+  Ok(
+    authorizations
+      .into_iter()
+      .map(|authorization| authorization.registry)
+      .collect(),
+  )
+}
+
+/// Writes `public.ecr.aws` credentials to the docker config file.
+pub async fn write_public_ecr_docker_config(
+  sdk_config: &SdkConfig,
+  config_file: Option<&Path>,
+) -> miette::Result<String, EcsHelperVarietyError> {
+  let authorization = EcrPublicClient::new(sdk_config).get_authorization().await?;
+
+  store_docker_config(std::slice::from_ref(&authorization), config_file)?;
+
+  Ok(authorization.registry)
 }
@@ -0,0 +1,212 @@
+use regex::Regex;
+
+use crate::errors::EcsHelperVarietyError;
+
+const REGEX_PREFIX: &str = "regex:";
+
+const DEFAULT_BRANCH_ENVIRONMENTS: [(&str, &str); 7] = [
+  ("master", "production"),
+  ("main", "production"),
+  ("qa", "qa"),
+  ("uat", "uat"),
+  ("staging", "staging"),
+  ("demo", "demo"),
+  ("sandbox", "sandbox"),
+];
+
+/// Compiles a glob (`*` and `?` wildcards) or, when prefixed with `regex:`, a regular expression
+/// matched against the whole name, wildcards are captured as groups.
+pub fn pattern_regex(pattern: &str) -> Result<Regex, regex::Error> {
+  let regex = match pattern.strip_prefix(REGEX_PREFIX) {
+    Some(regex) => format!("^(?:{regex})$"),
+    None => glob_to_regex(pattern),
+  };
+
+  Regex::new(&regex)
+}
+
+fn glob_to_regex(glob: &str) -> String {
+  let body = glob
+    .chars()
+    .map(|char| match char {
+      '*' => "(.*)".to_string(),
+      '?' => "(.)".to_string(),
+      char => regex::escape(&char.to_string()),
+    })
+    .collect::<String>();
+
+  format!("^{body}$")
+}
+
+/// Single `pattern=environment` rule.
+///
+/// Pattern is a glob (`*` and `?` wildcards) or, when prefixed with `regex:`, a regular expression.
+/// Both are matched against the whole branch name. Wildcards and regex groups are captured and
+/// could be referenced in the environment as `$1` (or `${1}` when followed by a letter).
+#[derive(Debug, Clone)]
+pub struct BranchRule {
+  pattern: Regex,
+  environment: String,
+}
+
+impl BranchRule {
+  pub fn new(pattern: &str, environment: &str) -> miette::Result<Self, EcsHelperVarietyError> {
+    let pattern = pattern_regex(pattern)
+      .map_err(|err| EcsHelperVarietyError::InvalidBranchPattern(format!("{pattern}: {err}")))?;
+
+    Ok(Self {
+      pattern,
+      environment: environment.to_string(),
+    })
+  }
+
+  /// Parses rule in the `pattern=environment` form.
+  pub fn parse(rule: &str) -> miette::Result<Self, EcsHelperVarietyError> {
+    let (pattern, environment) = rule.rsplit_once('=').ok_or_else(|| {
+      EcsHelperVarietyError::InvalidBranchPattern(format!("{rule}: expected pattern=environment"))
+    })?;
+
+    BranchRule::new(pattern.trim(), environment.trim())
+  }
+
+  fn environment_for(&self, branch: &str) -> Option<String> {
+    let captures = self.pattern.captures(branch)?;
+
+    let mut environment = String::new();
+    captures.expand(&self.environment, &mut environment);
+
+    Some(environment)
+  }
+}
+
+/// Ordered list of rules used to detect environment by the branch name, the first matched rule wins.
+#[derive(Debug, Clone)]
+pub struct BranchMapping {
+  rules: Vec<BranchRule>,
+}
+
+impl BranchMapping {
+  /// Creates mapping from the custom rules, default rules are checked after them.
+  pub fn new(rules: Vec<BranchRule>) -> Self {
+    let default_rules = BranchMapping::default().rules;
+
+    Self {
+      rules: rules.into_iter().chain(default_rules).collect(),
+    }
+  }
+
+  pub fn environment_for(&self, branch: &str) -> Option<String> {
+    self
+      .rules
+      .iter()
+      .find_map(|rule| rule.environment_for(branch))
+  }
+}
+
+impl Default for BranchMapping {
+  fn default() -> Self {
+    let rules = DEFAULT_BRANCH_ENVIRONMENTS
+      .iter()
+      .map(|(branch, environment)| {
+        BranchRule::new(branch, environment).expect("default branch patterns are valid")
+      })
+      .collect();
+
+    Self { rules }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_default_mapping() {
+    let mapping = BranchMapping::default();
+
+    assert_eq!(
+      mapping.environment_for("master").as_deref(),
+      Some("production")
+    );
+    assert_eq!(
+      mapping.environment_for("main").as_deref(),
+      Some("production")
+    );
+    assert_eq!(mapping.environment_for("qa").as_deref(), Some("qa"));
+    assert_eq!(mapping.environment_for("qa-fix"), None);
+    assert_eq!(mapping.environment_for("feature/qa"), None);
+  }
+
+  #[test]
+  fn test_glob_rules() {
+    let mapping = BranchMapping::new(vec![
+      BranchRule::parse("release/*=staging").unwrap(),
+      BranchRule::parse("hotfix/*=production").unwrap(),
+      BranchRule::parse("review/*=review-$1").unwrap(),
+      BranchRule::parse("preprod=preprod").unwrap(),
+    ]);
+
+    assert_eq!(
+      mapping.environment_for("release/2.3").as_deref(),
+      Some("staging")
+    );
+    assert_eq!(
+      mapping.environment_for("hotfix/login").as_deref(),
+      Some("production")
+    );
+    assert_eq!(
+      mapping.environment_for("review/checkout").as_deref(),
+      Some("review-checkout")
+    );
+    assert_eq!(
+      mapping.environment_for("preprod").as_deref(),
+      Some("preprod")
+    );
+    assert_eq!(
+      mapping.environment_for("master").as_deref(),
+      Some("production")
+    );
+    assert_eq!(mapping.environment_for("release"), None);
+  }
+
+  #[test]
+  fn test_regex_rules() {
+    let mapping = BranchMapping::new(vec![
+      BranchRule::parse("regex:review/(.*)=review-$1").unwrap(),
+      BranchRule::parse(r"regex:v(\d+)\.(\d+)=release-${1}x${2}").unwrap(),
+    ]);
+
+    assert_eq!(
+      mapping.environment_for("review/checkout").as_deref(),
+      Some("review-checkout")
+    );
+    assert_eq!(
+      mapping.environment_for("v2.3").as_deref(),
+      Some("release-2x3")
+    );
+    assert_eq!(mapping.environment_for("v2.3.1"), None);
+  }
+
+  #[test]
+  fn test_custom_rules_take_precedence() {
+    let mapping = BranchMapping::new(vec![BranchRule::parse("main=staging").unwrap()]);
+
+    assert_eq!(mapping.environment_for("main").as_deref(), Some("staging"));
+    assert_eq!(
+      mapping.environment_for("master").as_deref(),
+      Some("production")
+    );
+  }
+
+  #[test]
+  fn test_invalid_rules() {
+    assert!(matches!(
+      BranchRule::parse("release/*"),
+      Err(EcsHelperVarietyError::InvalidBranchPattern(_))
+    ));
+    assert!(matches!(
+      BranchRule::parse("regex:review/(.*=review"),
+      Err(EcsHelperVarietyError::InvalidBranchPattern(_))
+    ));
+  }
+}
@@ -0,0 +1,150 @@
+use crate::{args::CacheMode, errors::EcsHelperVarietyError};
+
+/// Driver of the default builder, it builds with the docker engine itself.
+const DOCKER_DRIVER: &str = "docker";
+
+/// Returns `docker buildx build` arguments which import and export the build cache of the repository.
+///
+/// ECR accepts the registry cache only as an OCI image manifest.
+/// The inline cache is imported from the latest image, there is nothing to import when it is not pushed.
+pub fn cache_args(
+  cache_mode: CacheMode,
+  repository: &str,
+  cache_tag: &str,
+  latest_tag: Option<&str>,
+) -> Vec<String> {
+  match cache_mode {
+    CacheMode::Registry => {
+      let cache_ref = format!("type=registry,ref={repository}:{cache_tag}");
+
+      vec![
+        "--cache-from".to_string(),
+        cache_ref.to_owned(),
+        "--cache-to".to_string(),
+        format!("{cache_ref},mode=max,image-manifest=true,oci-mediatypes=true"),
+      ]
+    }
+    CacheMode::Inline => {
+      let cache_from = latest_tag.into_iter().flat_map(|latest_tag| {
+        [
+          "--cache-from".to_string(),
+          format!("type=registry,ref={repository}:{latest_tag}"),
+        ]
+      });
+
+      cache_from
+        .chain(["--cache-to".to_string(), "type=inline".to_string()])
+        .collect()
+    }
+  }
+}
+
+/// Extracts the driver of the current builder from `docker buildx inspect` output.
+pub fn builder_driver(inspect_output: &str) -> Option<&str> {
+  inspect_output.lines().find_map(|line| {
+    let (key, value) = line.split_once(':')?;
+
+    (key.trim() == "Driver").then_some(value.trim())
+  })
+}
+
+/// Fails when the builder driver can't export the registry cache or build several platforms,
+/// the default `docker` driver supports neither.
+pub fn check_driver(
+  driver: &str,
+  cache_mode: Option<CacheMode>,
+  platforms: &[String],
+) -> miette::Result<(), EcsHelperVarietyError> {
+  if driver != DOCKER_DRIVER {
+    return Ok(());
+  }
+
+  let feature = if cache_mode == Some(CacheMode::Registry) {
+    "the registry cache"
+  } else if platforms.len() > 1 {
+    "multi-platform builds"
+  } else {
+    return Ok(());
+  };
+
+  Err(EcsHelperVarietyError::UnsupportedBuildxDriver {
+    driver: driver.to_string(),
+    feature: feature.to_string(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const REPOSITORY: &str = "123456789012.dkr.ecr.us-east-1.amazonaws.com/shop";
+
+  #[test]
+  fn test_registry_cache_args() {
+    assert_eq!(
+      cache_args(
+        CacheMode::Registry,
+        REPOSITORY,
+        "buildcache",
+        Some("latest")
+      ),
+      vec![
+        "--cache-from",
+        "type=registry,ref=123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:buildcache",
+        "--cache-to",
+        "type=registry,ref=123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:buildcache,mode=max,image-manifest=true,oci-mediatypes=true",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_inline_cache_args() {
+    assert_eq!(
+      cache_args(
+        CacheMode::Inline,
+        REPOSITORY,
+        "buildcache",
+        Some("production-latest")
+      ),
+      vec![
+        "--cache-from",
+        "type=registry,ref=123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:production-latest",
+        "--cache-to",
+        "type=inline",
+      ]
+    );
+    assert_eq!(
+      cache_args(CacheMode::Inline, REPOSITORY, "buildcache", None),
+      vec!["--cache-to", "type=inline"]
+    );
+  }
+
+  #[test]
+  fn test_builder_driver() {
+    let inspect_output = "Name:          ci\nDriver:        docker-container\nLast Activity: 2024-01-02 10:00:00 +0000 UTC\n\nNodes:\nName:     ci0\nEndpoint: unix:///var/run/docker.sock\n";
+
+    assert_eq!(builder_driver(inspect_output), Some("docker-container"));
+    assert_eq!(builder_driver("Name: default\n"), None);
+  }
+
+  #[test]
+  fn test_check_driver() {
+    let single_platform = vec!["linux/amd64".to_string()];
+    let platforms = vec!["linux/amd64".to_string(), "linux/arm64".to_string()];
+
+    assert!(check_driver("docker", None, &single_platform).is_ok());
+    assert!(check_driver("docker", Some(CacheMode::Inline), &single_platform).is_ok());
+    assert!(check_driver("docker-container", Some(CacheMode::Registry), &platforms).is_ok());
+
+    assert!(matches!(
+      check_driver("docker", Some(CacheMode::Registry), &single_platform),
+      Err(EcsHelperVarietyError::UnsupportedBuildxDriver { feature, .. })
+        if feature == "the registry cache"
+    ));
+    assert!(matches!(
+      check_driver("docker", Some(CacheMode::Inline), &platforms),
+      Err(EcsHelperVarietyError::UnsupportedBuildxDriver { feature, .. })
+        if feature == "multi-platform builds"
+    ));
+  }
+}
@@ -0,0 +1,568 @@
+use std::time::Duration;
+
+use aws_config::{Region, SdkConfig};
+use aws_sdk_cloudwatchlogs::{
+  Client,
+  types::{FilteredLogEvent, OutputLogEvent},
+};
+use aws_sdk_ecs::types::{ContainerDefinition, LogDriver};
+
+use crate::errors::EcsHelperVarietyError;
+
+const AWSLOGS_GROUP_OPTION: &str = "awslogs-group";
+const AWSLOGS_REGION_OPTION: &str = "awslogs-region";
+const AWSLOGS_STREAM_PREFIX_OPTION: &str = "awslogs-stream-prefix";
+/// `FilterLogEvents` accepts at most 100 log stream names.
+const MAX_LOG_STREAMS_PER_REQUEST: usize = 100;
+
+/// Events are ingested with a delay and lagging tasks write older timestamps, so `--follow` keeps
+/// querying this window and skips already printed events by id.
+const FOLLOW_LOOKBACK: Duration = Duration::from_secs(60);
+
+/// Where the `awslogs` driver writes logs of a container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AwsLogsConfiguration {
+  pub group: String,
+  pub region: Option<String>,
+  pub stream_prefix: Option<String>,
+}
+
+impl AwsLogsConfiguration {
+  /// Extracts `awslogs` options from the container definition.
+  pub fn from_container_definition(container_definition: &ContainerDefinition) -> Option<Self> {
+    let log_configuration = container_definition.log_configuration()?;
+
+    if log_configuration.log_driver() != &LogDriver::Awslogs {
+      return None;
+    }
+
+    let options = log_configuration.options()?;
+
+    Some(Self {
+      group: options.get(AWSLOGS_GROUP_OPTION)?.to_owned(),
+      region: options.get(AWSLOGS_REGION_OPTION).cloned(),
+      stream_prefix: options.get(AWSLOGS_STREAM_PREFIX_OPTION).cloned(),
+    })
+  }
+
+  /// Log stream name is `prefix/container-name/task-id`, it is known only if the stream prefix is set.
+  pub fn log_stream_name(&self, container_name: &str, task_arn: &str) -> Option<String> {
+    let stream_prefix = self.stream_prefix.as_ref()?;
+
+    Some(format!(
+      "{stream_prefix}/{container_name}/{}",
+      task_id(task_arn)
+    ))
+  }
+}
+
+/// Returns `awslogs` configurations of the containers whose name contains `container`, all when it is not set.
+pub fn awslogs_configurations(
+  container_definitions: &[ContainerDefinition],
+  container: Option<&str>,
+) -> Vec<(String, AwsLogsConfiguration)> {
+  container_definitions
+    .iter()
+    .filter(|container_definition| match container {
+      Some(container) => container_definition
+        .name()
+        .is_some_and(|name| name.contains(container)),
+      None => true,
+    })
+    .filter_map(|container_definition| {
+      let name = container_definition.name()?.to_owned();
+      let configuration = AwsLogsConfiguration::from_container_definition(container_definition)?;
+
+      Some((name, configuration))
+    })
+    .collect()
+}
+
+/// Returns the start time (in milliseconds) of the next `--follow` query, it never moves before
+/// `start_time` and covers the lookback window before `now`.
+pub fn follow_start_time(start_time: i64, now: Duration) -> i64 {
+  let lookback_start_time = now.saturating_sub(FOLLOW_LOOKBACK).as_millis() as i64;
+
+  start_time.max(lookback_start_time)
+}
+
+/// Task id is the last segment of the task ARN.
+pub fn task_id(task_arn: &str) -> &str {
+  task_arn.rsplit('/').next().unwrap_or(task_arn)
+}
+
+/// Parses durations like `30s`, `10m`, `2h` or `1d`.
+pub fn parse_duration(value: &str) -> miette::Result<Duration, EcsHelperVarietyError> {
+  let invalid_duration = || EcsHelperVarietyError::InvalidDuration(value.to_string());
+
+  let split_at = value
+    .find(|char: char| !char.is_ascii_digit())
+    .ok_or_else(invalid_duration)?;
+  let (amount, unit) = value.split_at(split_at);
+  let amount = amount.parse::<u64>().map_err(|_| invalid_duration())?;
+
+  let unit_seconds = match unit {
+    "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    "d" => 60 * 60 * 24,
+    _ => return Err(invalid_duration()),
+  };
+  let seconds = amount
+    .checked_mul(unit_seconds)
+    .ok_or_else(invalid_duration)?;
+
+  Ok(Duration::from_secs(seconds))
+}
+
+pub struct CloudWatchLogsClient {
+  client: Client,
+}
+
+impl CloudWatchLogsClient {
+  pub fn new(config: &SdkConfig) -> Self {
+    Self {
+      client: Client::new(config),
+    }
+  }
+
+  /// Creates client for the region from `awslogs-region` if it differs from the SDK config one.
+  pub fn for_region(config: &SdkConfig, region: Option<&String>) -> Self {
+    match region {
+      Some(region) => {
+        let config = config
+          .to_builder()
+          .region(Region::new(region.to_owned()))
+          .build();
+
+        CloudWatchLogsClient::new(&config)
+      }
+      None => CloudWatchLogsClient::new(config),
+    }
+  }
+
+  /// Returns log events after `next_token` and the token to continue from.
+  ///
+  /// Stream is created by ECS only when the container starts, so missing stream means there are no events yet.
+  pub async fn get_log_events(
+    &self,
+    log_group: &str,
+    log_stream: &str,
+    next_token: Option<String>,
+  ) -> miette::Result<(Vec<OutputLogEvent>, Option<String>), EcsHelperVarietyError> {
+    let response = self
+      .client
+      .get_log_events()
+      .log_group_name(log_group)
+      .log_stream_name(log_stream)
+      .start_from_head(true)
+      .set_next_token(next_token.clone())
+      .send()
+      .await;
+
+    let response = match response {
+      Ok(response) => response,
+      Err(error)
+        if error
+          .as_service_error()
+          .is_some_and(|error| error.is_resource_not_found_exception()) =>
+      {
+        return Ok((vec![], next_token));
+      }
+      Err(error) => return Err(EcsHelperVarietyError::GetLogEventsError(Box::new(error))),
+    };
+
+    let next_token = response
+      .next_forward_token()
+      .map(str::to_string)
+      .or(next_token);
+
+    Ok((response.events().to_vec(), next_token))
+  }
+
+  /// Returns events of the given streams starting from `start_time` (in milliseconds).
+  pub async fn filter_log_events(
+    &self,
+    log_group: &str,
+    log_streams: &[String],
+    start_time: i64,
+    filter_pattern: Option<&String>,
+  ) -> miette::Result<Vec<FilteredLogEvent>, EcsHelperVarietyError> {
+    let mut events = vec![];
+
+    for log_streams in log_streams.chunks(MAX_LOG_STREAMS_PER_REQUEST) {
+      let pages = self
+        .client
+        .filter_log_events()
+        .log_group_name(log_group)
+        .set_log_stream_names(Some(log_streams.to_vec()))
+        .start_time(start_time)
+        .set_filter_pattern(filter_pattern.cloned())
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await
+        .map_err(|error| EcsHelperVarietyError::FilterLogEventsError(Box::new(error)))?;
+
+      events.extend(
+        pages
+          .into_iter()
+          .flat_map(|page| page.events.unwrap_or_default()),
+      );
+    }
+
+    Ok(events)
+  }
+}
+
+/// Follows a single log stream, every call returns only events which were not returned before.
+pub struct LogStreamTail {
+  client: CloudWatchLogsClient,
+  log_group: String,
+  log_stream: String,
+  next_token: Option<String>,
+}
+
+impl LogStreamTail {
+  pub fn new(client: CloudWatchLogsClient, log_group: String, log_stream: String) -> Self {
+    Self {
+      client,
+      log_group,
+      log_stream,
+      next_token: None,
+    }
+  }
+
+  pub fn log_stream(&self) -> &str {
+    &self.log_stream
+  }
+
+  pub async fn next_events(
+    &mut self,
+  ) -> miette::Result<Vec<OutputLogEvent>, EcsHelperVarietyError> {
+    let mut events = vec![];
+
+    // one response is limited to 1MB, so keep reading until the token stops moving
+    loop {
+      let (page, next_token) = self
+        .client
+        .get_log_events(&self.log_group, &self.log_stream, self.next_token.clone())
+        .await?;
+      let is_last_page = page.is_empty() || next_token == self.next_token;
+
+      events.extend(page);
+      self.next_token = next_token;
+
+      if is_last_page {
+        return Ok(events);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aws_config::BehaviorVersion;
+  use aws_sdk_cloudwatchlogs::config::{Credentials, SharedCredentialsProvider};
+  use aws_sdk_ecs::types::LogConfiguration;
+  use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
+  use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+  use aws_smithy_types::body::SdkBody;
+
+  fn sdk_config(events: Vec<ReplayEvent>) -> SdkConfig {
+    sdk_config_with(StaticReplayClient::new(events))
+  }
+
+  fn sdk_config_with(http_client: StaticReplayClient) -> SdkConfig {
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client)
+      .build()
+  }
+
+  #[test]
+  fn test_awslogs_configuration() {
+    let container_definition = ContainerDefinition::builder()
+      .name("web")
+      .log_configuration(
+        LogConfiguration::builder()
+          .log_driver(LogDriver::Awslogs)
+          .options("awslogs-group", "/ecs/shop")
+          .options("awslogs-region", "eu-west-1")
+          .options("awslogs-stream-prefix", "web-migrate")
+          .build()
+          .unwrap(),
+      )
+      .build();
+
+    let configuration =
+      AwsLogsConfiguration::from_container_definition(&container_definition).unwrap();
+
+    assert_eq!(configuration.group, "/ecs/shop");
+    assert_eq!(configuration.region.as_deref(), Some("eu-west-1"));
+    assert_eq!(
+      configuration
+        .log_stream_name(
+          "web-migrate",
+          "arn:aws:ecs:us-east-1:123456789012:task/cluster/0123456789abcdef"
+        )
+        .as_deref(),
+      Some("web-migrate/web-migrate/0123456789abcdef")
+    );
+  }
+
+  #[test]
+  fn test_awslogs_configuration_for_other_driver() {
+    let container_definition = ContainerDefinition::builder()
+      .name("web")
+      .log_configuration(
+        LogConfiguration::builder()
+          .log_driver(LogDriver::Fluentd)
+          .build()
+          .unwrap(),
+      )
+      .build();
+
+    assert_eq!(
+      AwsLogsConfiguration::from_container_definition(&container_definition),
+      None
+    );
+  }
+
+  #[test]
+  fn test_parse_duration() {
+    assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+    assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+    assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+    assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+
+    for value in ["", "10", "m", "10w", "-1m", "1.5h", "18446744073709551615d"] {
+      assert!(matches!(
+        parse_duration(value),
+        Err(EcsHelperVarietyError::InvalidDuration(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn test_follow_start_time() {
+    let now = Duration::from_secs(1_700_000_600);
+
+    // the window stays behind now, so late events are queried again
+    assert_eq!(follow_start_time(1_700_000_000_000, now), 1_700_000_540_000);
+    // the first query of --since is not extended
+    assert_eq!(follow_start_time(1_700_000_580_000, now), 1_700_000_580_000);
+  }
+
+  #[tokio::test]
+  async fn test_filter_log_events() {
+    let first_page = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        r#"
+          {
+            "events": [
+              { "logStreamName": "web/web/first", "timestamp": 1700000000000, "message": "GET /", "eventId": "1" }
+            ],
+            "nextToken": "page-2"
+          }
+        "#,
+      ))
+      .unwrap();
+    let second_page = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        r#"
+          {
+            "events": [
+              { "logStreamName": "web/web/second", "timestamp": 1700000000500, "message": "GET /health", "eventId": "2" }
+            ]
+          }
+        "#,
+      ))
+      .unwrap();
+    let sdk_config = sdk_config(vec![
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), first_page),
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), second_page),
+    ]);
+
+    let client = CloudWatchLogsClient::new(&sdk_config);
+    let events = client
+      .filter_log_events(
+        "/ecs/shop",
+        &["web/web/first".to_string(), "web/web/second".to_string()],
+        1700000000000,
+        None,
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].message(), Some("GET /health"));
+  }
+
+  fn container_definition(name: &str) -> ContainerDefinition {
+    ContainerDefinition::builder()
+      .name(name)
+      .log_configuration(
+        LogConfiguration::builder()
+          .log_driver(LogDriver::Awslogs)
+          .options("awslogs-group", "/ecs/shop")
+          .options("awslogs-stream-prefix", "shop")
+          .build()
+          .unwrap(),
+      )
+      .build()
+  }
+
+  fn empty_page() -> ReplayEvent {
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(r#"{"events": []}"#))
+      .unwrap();
+
+    ReplayEvent::new(HttpRequest::new(SdkBody::from("")), response)
+  }
+
+  fn requested_log_streams(http_client: &StaticReplayClient) -> Vec<Vec<String>> {
+    http_client
+      .actual_requests()
+      .map(|request| {
+        let body: serde_json::Value =
+          serde_json::from_slice(request.body().bytes().unwrap()).unwrap();
+
+        serde_json::from_value(body["logStreamNames"].clone()).unwrap()
+      })
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn test_filter_log_events_in_chunks_of_streams() {
+    let http_client = StaticReplayClient::new(vec![empty_page(), empty_page()]);
+    let client = CloudWatchLogsClient::new(&sdk_config_with(http_client.clone()));
+    let log_streams = (0..150)
+      .map(|index| format!("shop/web/{index}"))
+      .collect::<Vec<_>>();
+
+    client
+      .filter_log_events("/ecs/shop", &log_streams, 1700000000000, None)
+      .await
+      .unwrap();
+
+    let requested_log_streams = requested_log_streams(&http_client);
+    assert_eq!(requested_log_streams.len(), 2);
+    assert_eq!(requested_log_streams[0], log_streams[..100]);
+    assert_eq!(requested_log_streams[1], log_streams[100..]);
+  }
+
+  #[tokio::test]
+  async fn test_filter_log_events_of_container() {
+    let http_client = StaticReplayClient::new(vec![empty_page()]);
+    let client = CloudWatchLogsClient::new(&sdk_config_with(http_client.clone()));
+    let container_definitions = vec![
+      container_definition("web"),
+      container_definition("worker"),
+      ContainerDefinition::builder().name("web-sidecar").build(),
+    ];
+
+    let log_streams = awslogs_configurations(&container_definitions, Some("web"))
+      .iter()
+      .filter_map(|(container_name, configuration)| {
+        configuration.log_stream_name(
+          container_name,
+          "arn:aws:ecs:us-east-1:123456789012:task/cluster/0123456789abcdef",
+        )
+      })
+      .collect::<Vec<_>>();
+
+    client
+      .filter_log_events(
+        "/ecs/shop",
+        &log_streams,
+        1700000000000,
+        Some(&"ERROR".to_string()),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(
+      requested_log_streams(&http_client),
+      [["shop/web/0123456789abcdef"]]
+    );
+    assert_eq!(
+      awslogs_configurations(&container_definitions, None).len(),
+      2
+    );
+  }
+
+  #[tokio::test]
+  async fn test_get_log_events() {
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        r#"
+          {
+            "events": [
+              { "timestamp": 1700000000000, "message": "Running migrations", "ingestionTime": 1700000000001 },
+              { "timestamp": 1700000001000, "message": "Done", "ingestionTime": 1700000001001 }
+            ],
+            "nextForwardToken": "f/2",
+            "nextBackwardToken": "b/1"
+          }
+        "#,
+      ))
+      .unwrap();
+    let sdk_config = sdk_config(vec![ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      response,
+    )]);
+
+    let client = CloudWatchLogsClient::new(&sdk_config);
+    let (events, next_token) = client
+      .get_log_events("/ecs/shop", "web/web/0123456789abcdef", None)
+      .await
+      .unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].message(), Some("Running migrations"));
+    assert_eq!(next_token.as_deref(), Some("f/2"));
+  }
+
+  #[tokio::test]
+  async fn test_get_log_events_for_missing_stream() {
+    let response = http::Response::builder()
+      .status(400)
+      .header("x-amzn-query-error", "ResourceNotFoundException;Sender")
+      .body(SdkBody::from(
+        r#"
+          {
+            "__type": "ResourceNotFoundException",
+            "message": "The specified log stream does not exist."
+          }
+        "#,
+      ))
+      .unwrap();
+    let sdk_config = sdk_config(vec![ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      response,
+    )]);
+
+    let client = CloudWatchLogsClient::new(&sdk_config);
+    let (events, next_token) = client
+      .get_log_events(
+        "/ecs/shop",
+        "web/web/0123456789abcdef",
+        Some("f/1".to_string()),
+      )
+      .await
+      .unwrap();
+
+    assert!(events.is_empty());
+    assert_eq!(next_token.as_deref(), Some("f/1"));
+  }
+}
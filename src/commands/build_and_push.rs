@@ -1,15 +1,54 @@
+use aws_sdk_ecr::types::ImageIdentifier;
 use ecs_helpers::{
-  Command, args::BuildAndPushCommandArguments, auth, config::Config, ecr::EcrClient,
+  Command,
+  args::{BuildAndPushCommandArguments, BuildBackend, CacheMode, ContainerEngine},
+  auth, buildx,
+  config::Config,
+  container_engine, dockerfile,
+  ecr::{EcrClient, EcrRegistry},
+  ecr_public::EcrPublicClient,
   errors::EcsHelperVarietyError,
+  image_scan::{self, ScanGate},
+  image_tags, manifest_list,
 };
-use futures::try_join;
-use std::process::Stdio;
+use futures::future::try_join_all;
+use std::{collections::HashSet, process::Stdio};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command as TokioCommand;
 
+/// Repository fields used to find the target repository, private and public repositories share them.
+struct RepositorySummary {
+  arn: String,
+  name: String,
+  uri: String,
+}
+
+impl RepositorySummary {
+  fn new(arn: Option<&str>, name: Option<&str>, uri: Option<&str>) -> Option<Self> {
+    Some(Self {
+      arn: arn?.to_string(),
+      name: name?.to_string(),
+      uri: uri?.to_string(),
+    })
+  }
+}
+
+/// Returns the repository name of the repository URI, e.g. `shop` of `123456789012.dkr.ecr.us-east-1.amazonaws.com/shop`.
+fn repository_name(repository: &str) -> &str {
+  repository
+    .split_once('/')
+    .map_or(repository, |(_, repository_name)| repository_name)
+}
+
+/// Returns the repository name of the public repository URI, e.g. `shop` of `public.ecr.aws/alias/shop`.
+fn public_repository_name(repository: &str) -> &str {
+  repository_name(repository_name(repository))
+}
+
 pub struct BuildAndPushCommand {
   config: Config,
   ecr_client: EcrClient,
+  ecr_public_client: EcrPublicClient,
   image: String,
   should_cache: bool,
   build_arg: Option<Vec<String>>,
@@ -18,81 +57,123 @@ pub struct BuildAndPushCommand {
   repository: Option<String>,
   target: Option<String>,
   need_stdout: bool,
-  platform: String,
+  platforms: Vec<String>,
+  scan_gate: Option<ScanGate>,
+  public: bool,
+  backend: BuildBackend,
+  cache_mode: CacheMode,
+  cache_tag: String,
+  engine: ContainerEngine,
+  tag_templates: Vec<String>,
+  latest_tag: Option<String>,
+  force: bool,
+  retag_existing: bool,
 }
 
 impl BuildAndPushCommand {
   pub fn new(config: Config, args: BuildAndPushCommandArguments) -> Self {
     let ecr_client = EcrClient::new(&config.sdk_config);
+    let ecr_public_client = EcrPublicClient::new(&config.sdk_config);
 
     Self {
-      config,
       ecr_client,
+      ecr_public_client,
       image: args.image,
       should_cache: args.cache,
-      build_arg: args.build_arg,
+      build_arg: args.build_arg.or(config.build_args.to_owned()),
       directory: args.directory,
       file: args.file,
       repository: args.repository,
       target: args.target,
       need_stdout: args.need_stdout,
-      platform: args.platform,
+      platforms: args.platform,
+      scan_gate: ScanGate::from_args(&args.image_scan),
+      public: args.public,
+      backend: args.backend,
+      cache_mode: args.cache_mode,
+      cache_tag: args.cache_tag,
+      engine: container_engine::resolve(args.engine),
+      tag_templates: args.tag.unwrap_or_default(),
+      latest_tag: (!args.no_latest).then_some(args.latest_tag),
+      force: args.force,
+      retag_existing: args.retag_existing,
+      config,
     }
   }
 
+  async fn get_repositories(
+    &self,
+  ) -> miette::Result<Vec<RepositorySummary>, EcsHelperVarietyError> {
+    let repositories = if self.public {
+      self
+        .ecr_public_client
+        .get_repositories()
+        .await?
+        .iter()
+        .filter_map(|repo| {
+          RepositorySummary::new(
+            repo.repository_arn(),
+            repo.repository_name(),
+            repo.repository_uri(),
+          )
+        })
+        .collect()
+    } else {
+      self
+        .ecr_client
+        .get_private_repositories()
+        .await?
+        .iter()
+        .filter_map(|repo| {
+          RepositorySummary::new(
+            repo.repository_arn(),
+            repo.repository_name(),
+            repo.repository_uri(),
+          )
+        })
+        .collect()
+    };
+
+    Ok(repositories)
+  }
+
   async fn get_repository(&self) -> miette::Result<String, EcsHelperVarietyError> {
-    let repository = self.ecr_client.get_private_repositories().await?;
+    let repository = self.get_repositories().await?;
 
     let with_name = repository
       .iter()
-      .filter_map(|repo| {
-        let arn = repo.repository_arn()?;
-
-        if arn.contains(&self.image) {
-          Some(repo)
-        } else {
-          None
-        }
-      })
+      .filter(|repo| repo.arn.contains(&self.image))
       .collect::<Vec<_>>();
 
     if with_name.len() == 1 {
-      let repository = with_name[0].repository_uri().unwrap().to_string();
-      return Ok(repository);
+      return Ok(with_name[0].uri.to_owned());
     }
 
     let exact = with_name
       .iter()
-      .filter_map(|repo| {
-        let arn = repo.repository_arn()?;
-
-        if let Some(repository) = &self.repository {
-          if arn.contains(repository) {
-            return Some(repo);
-          }
+      .filter(|repo| {
+        if let Some(repository) = &self.repository
+          && repo.arn.contains(repository)
+        {
+          return true;
         }
 
-        let same_project = arn.contains(&self.config.project);
-        let same_application = arn.contains(&self.config.application);
+        let same_project = repo.arn.contains(&self.config.project);
+        let same_application = repo.arn.contains(&self.config.application);
 
-        if same_project && same_application {
-          Some(repo)
-        } else {
-          None
-        }
+        same_project && same_application
       })
       .collect::<Vec<_>>();
 
     if exact.len() == 1 {
-      let repository = exact[0].repository_uri().unwrap().to_string();
-      return Ok(repository);
+      return Ok(exact[0].uri.to_owned());
     }
 
     if exact.len() > 1 {
       return Err(EcsHelperVarietyError::MultipleRepositoriesFound(
         exact
           .iter()
-          .map(|repo| repo.repository_name().unwrap().to_string())
+          .map(|repo| repo.name.to_owned())
           .collect::<Vec<String>>()
           .join(", "),
       ));
@@ -101,13 +182,67 @@ impl BuildAndPushCommand {
     Err(EcsHelperVarietyError::NoRepositoryFound)
   }
 
+  /// Renders the tags to push, the version tag goes first and the latest tag last.
+  fn tags(&self) -> miette::Result<(Vec<String>, Option<String>), EcsHelperVarietyError> {
+    let tag_context = &self.config.tag_context;
+
+    let latest_tag = self
+      .latest_tag
+      .as_deref()
+      .map(|template| image_tags::render(template, tag_context))
+      .transpose()?;
+
+    let mut tags = vec![self.config.version.to_owned()];
+    for template in &self.tag_templates {
+      tags.push(image_tags::render(template, tag_context)?);
+    }
+    tags.extend(latest_tag.to_owned());
+
+    let mut seen = HashSet::new();
+    tags.retain(|tag| seen.insert(tag.to_owned()));
+
+    Ok((tags, latest_tag))
+  }
+
+  /// Logs in to ECR registries of the base images which are not in the account of the config.
+  async fn login_to_base_image_registries(&self) -> miette::Result<(), EcsHelperVarietyError> {
+    let content = match std::fs::read_to_string(&self.file) {
+      Ok(content) => content,
+      Err(err) => {
+        log::warn!(
+          "Failed to read {} for base image registries: {err}",
+          self.file
+        );
+        return Ok(());
+      }
+    };
+
+    let config_registry = EcrRegistry {
+      account_id: self.config.aws_account_id.to_owned(),
+      region: Some(self.config.region.to_string()),
+    };
+    let registries = dockerfile::ecr_registries(&content)
+      .into_iter()
+      .filter(|registry| *registry != config_registry)
+      .collect::<Vec<_>>();
+
+    if registries.is_empty() {
+      return Ok(());
+    }
+
+    auth::login_to_registries(self.engine, &self.config.sdk_config, &registries).await?;
+
+    Ok(())
+  }
+
   async fn pull_image_to_cache(
     &self,
     repository: &String,
+    latest_tag: &str,
   ) -> miette::Result<(), EcsHelperVarietyError> {
-    let latest_tag = format!("{}:latest", repository);
+    let latest_tag = format!("{}:{}", repository, latest_tag);
 
-    let output = TokioCommand::new("docker")
+    let output = container_engine::command(self.engine)
       .arg("pull")
       .arg(latest_tag)
       .output()
@@ -122,11 +257,42 @@ impl BuildAndPushCommand {
     Ok(())
   }
 
-  async fn build(&self, repository: &String) -> miette::Result<(), EcsHelperVarietyError> {
-    let mut command = TokioCommand::new("docker");
+  /// Checks that the current buildx builder supports the cache mode and platforms of the build.
+  async fn check_buildx_builder(&self) -> miette::Result<(), EcsHelperVarietyError> {
+    let output = container_engine::command(self.engine)
+      .args(["buildx", "inspect"])
+      .output()
+      .await?;
+
+    if !output.status.success() {
+      return Err(EcsHelperVarietyError::BuildImageError(String::from_utf8(
+        output.stderr,
+      )?));
+    }
+
+    let inspect_output = String::from_utf8(output.stdout)?;
+    let Some(driver) = buildx::builder_driver(&inspect_output) else {
+      return Ok(());
+    };
+    let cache_mode = self.should_cache.then_some(self.cache_mode);
+
+    buildx::check_driver(driver, cache_mode, &self.platforms)
+  }
+
+  async fn build(
+    &self,
+    repository: &str,
+    platform: &str,
+    tags: &[String],
+    latest_tag: Option<&str>,
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let mut command = container_engine::command(self.engine);
+    if self.backend == BuildBackend::Buildx {
+      command.arg("buildx");
+    }
     command.arg("build");
     command.arg(self.directory.clone());
-    command.arg(format!("--platform={}", self.platform.clone()));
+    command.arg(format!("--platform={}", platform));
     command.arg(format!("--file={}", self.file.clone()));
 
     if let Some(build_arg) = &self.build_arg {
@@ -140,17 +306,33 @@ impl BuildAndPushCommand {
     }
 
     if self.should_cache {
-      command.arg("--cache-from");
-      command.arg(format!("{}:latest", repository));
+      match self.backend {
+        BuildBackend::Docker => {
+          if let Some(latest_tag) = latest_tag {
+            command.arg("--cache-from");
+            command.arg(format!("{}:{}", repository, latest_tag));
+          }
+        }
+        BuildBackend::Buildx => {
+          command.args(buildx::cache_args(
+            self.cache_mode,
+            repository,
+            &self.cache_tag,
+            latest_tag,
+          ));
+        }
+      }
     }
 
-    let latest_tag: String = format!("{}:latest", repository);
-    let version_tag: String = format!("{}:{}", repository, self.config.version);
+    for tag in tags {
+      command.arg("-t");
+      command.arg(tag);
+    }
 
-    command.arg("-t");
-    command.arg(&version_tag);
-    command.arg("-t");
-    command.arg(&latest_tag);
+    // buildx pushes the image itself, the image is not loaded into the local docker
+    if self.backend == BuildBackend::Buildx {
+      command.arg("--push");
+    }
 
     if self.need_stdout {
       self.print_stdout(&mut command).await?;
@@ -158,7 +340,7 @@ impl BuildAndPushCommand {
 
     let output = command.output().await?;
 
-    log::info!("Building with two tags: {} & {}", latest_tag, version_tag);
+    log::info!("Building {} with tags: {}", platform, tags.join(" & "));
 
     if !output.status.success() {
       return Err(EcsHelperVarietyError::BuildImageError(String::from_utf8(
@@ -169,44 +351,178 @@ impl BuildAndPushCommand {
     Ok(())
   }
 
-  async fn push(&self, repository: &String) -> miette::Result<(), EcsHelperVarietyError> {
-    let latest_tag: String = format!("{}:latest", repository);
-    let version_tag: String = format!("{}:{}", repository, self.config.version);
+  async fn push(&self, tags: &[String]) -> miette::Result<(), EcsHelperVarietyError> {
+    log::info!("Pushing with tags: {}", tags.join(" & "));
+
+    try_join_all(tags.iter().map(|tag| async move {
+      let push_output = container_engine::command(self.engine)
+        .arg("push")
+        .arg(tag)
+        .output()
+        .await?;
+
+      if !push_output.status.success() {
+        return Err(EcsHelperVarietyError::PushImageError(format!(
+          "Failed to push image with tag {}",
+          tag
+        )));
+      }
 
-    let mut push_latest_command = TokioCommand::new("docker");
-    push_latest_command.arg("push").arg(&latest_tag);
+      Ok(())
+    }))
+    .await?;
 
-    let mut push_version_command = TokioCommand::new("docker");
-    push_version_command.arg("push").arg(&version_tag);
+    Ok(())
+  }
 
-    log::info!("Pushing with two tags: {} & {}", latest_tag, version_tag);
+  /// Builds every platform separately and joins the pushed images into manifest lists,
+  /// a classic build can't produce a manifest list itself.
+  async fn build_platforms(
+    &self,
+    repository: &str,
+    tags: &[String],
+    latest_tag: Option<&str>,
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let platform_tags = self
+      .platforms
+      .iter()
+      .map(|platform| {
+        format!(
+          "{repository}:{}",
+          manifest_list::platform_tag(&self.config.version, platform)
+        )
+      })
+      .collect::<Vec<_>>();
 
-    try_join!(
-      async {
-        let push_latest_output = push_latest_command.output().await?;
-        if !push_latest_output.status.success() {
-          return Err(EcsHelperVarietyError::PushImageError(format!(
-            "Failed to push image with tag {}",
-            latest_tag
-          )));
-        }
-        Ok(())
-      },
-      async {
-        let push_version_output = push_version_command.output().await?;
-        if !push_version_output.status.success() {
-          return Err(EcsHelperVarietyError::PushImageError(format!(
-            "Failed to push image with tag {}",
-            version_tag
-          )));
-        }
-        Ok(())
-      }
-    )?;
+    for (platform, platform_tag) in self.platforms.iter().zip(&platform_tags) {
+      self
+        .build(
+          repository,
+          platform,
+          std::slice::from_ref(platform_tag),
+          latest_tag,
+        )
+        .await?;
+    }
+
+    self.push(&platform_tags).await?;
+
+    for tag in tags {
+      self.push_manifest_list(tag, &platform_tags).await?;
+    }
+
+    Ok(())
+  }
+
+  async fn push_manifest_list(
+    &self,
+    tag: &str,
+    platform_tags: &[String],
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    log::info!(
+      "Pushing manifest list {} of {}",
+      tag,
+      platform_tags.join(" & ")
+    );
+
+    let create_output = container_engine::command(self.engine)
+      .args(container_engine::manifest_create_args(
+        self.engine,
+        tag,
+        platform_tags,
+      )?)
+      .output()
+      .await?;
+
+    if !create_output.status.success() {
+      return Err(EcsHelperVarietyError::PushImageError(String::from_utf8(
+        create_output.stderr,
+      )?));
+    }
+
+    let push_output = container_engine::command(self.engine)
+      .args(container_engine::manifest_push_args(self.engine, tag)?)
+      .output()
+      .await?;
+
+    if !push_output.status.success() {
+      return Err(EcsHelperVarietyError::PushImageError(String::from_utf8(
+        push_output.stderr,
+      )?));
+    }
 
     Ok(())
   }
 
+  /// Adds the tags to the already pushed image of the version.
+  async fn retag_existing_image(
+    &self,
+    repository: &str,
+    tags: &[String],
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let repository_name = repository_name(repository);
+    let image = self
+      .ecr_client
+      .get_image(
+        repository_name,
+        ImageIdentifier::builder()
+          .image_tag(&self.config.version)
+          .build(),
+      )
+      .await?;
+
+    for tag in tags.iter().filter(|tag| **tag != self.config.version) {
+      self
+        .ecr_client
+        .tag_image(repository_name, &image, tag)
+        .await?;
+      log::info!("Tagged {}:{} as {}", repository, self.config.version, tag);
+    }
+
+    Ok(())
+  }
+
+  /// Checks if the version tag has already been pushed to the repository.
+  async fn version_exists(&self, repository: &str) -> miette::Result<bool, EcsHelperVarietyError> {
+    if self.public {
+      return self
+        .ecr_public_client
+        .image_exists(public_repository_name(repository), &self.config.version)
+        .await;
+    }
+
+    self
+      .ecr_client
+      .image_exists(
+        repository_name(repository),
+        ImageIdentifier::builder()
+          .image_tag(&self.config.version)
+          .build(),
+      )
+      .await
+  }
+
+  /// Waits for the scan of the pushed image, every platform image of a manifest list is checked.
+  async fn check_image_scan(
+    &self,
+    scan_gate: &ScanGate,
+    repository: &str,
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let image_id = ImageIdentifier::builder()
+      .image_tag(&self.config.version)
+      .build();
+    let image = format!("{repository}:{}", self.config.version);
+
+    image_scan::wait_for_image_scans(
+      &self.ecr_client,
+      scan_gate,
+      repository_name(repository),
+      image_id,
+      &image,
+    )
+    .await
+  }
+
   async fn print_stdout(
     &self,
     command: &mut TokioCommand,
@@ -254,8 +570,37 @@ impl Command for BuildAndPushCommand {
       ..
     } = &self.config;
 
+    if self.backend == BuildBackend::Buildx {
+      if self.engine != ContainerEngine::Docker {
+        return Err(container_engine::unsupported(self.engine, "buildx"));
+      }
+
+      self.check_buildx_builder().await?;
+    }
+
     let repository = self.get_repository().await?;
-    let auth_output = auth::login_to_ecr(sdk_config, region, aws_account_id).await?;
+    let (tags, latest_tag) = self.tags()?;
+
+    let version_exists = !self.force && self.version_exists(&repository).await?;
+
+    if version_exists {
+      println!(
+        "Image {}:{} already exists, skipping the build",
+        repository, self.config.version
+      );
+
+      if self.retag_existing {
+        self.retag_existing_image(&repository, &tags).await?;
+      }
+
+      if let Some(scan_gate) = &self.scan_gate {
+        self.check_image_scan(scan_gate, &repository).await?;
+      }
+
+      return Ok(());
+    }
+
+    let auth_output = auth::login_to_ecr(self.engine, sdk_config, region, aws_account_id).await?;
 
     if !auth_output.status.success() {
       return Err(EcsHelperVarietyError::LoginFailed(format!(
@@ -264,12 +609,43 @@ impl Command for BuildAndPushCommand {
       )));
     }
 
-    if self.should_cache {
-      self.pull_image_to_cache(&repository).await?;
+    if self.public {
+      auth::login_to_public_ecr(self.engine, sdk_config).await?;
+    }
+
+    self.login_to_base_image_registries().await?;
+
+    let latest_tag = latest_tag.as_deref();
+    let tags = tags
+      .iter()
+      .map(|tag| format!("{}:{}", repository, tag))
+      .collect::<Vec<_>>();
+
+    match self.backend {
+      BuildBackend::Docker => {
+        match (self.should_cache, latest_tag) {
+          (true, Some(latest_tag)) => self.pull_image_to_cache(&repository, latest_tag).await?,
+          (true, None) => log::warn!("Cache is not used, there is no latest tag to pull"),
+          (false, _) => {}
+        }
+
+        if let [platform] = self.platforms.as_slice() {
+          self.build(&repository, platform, &tags, latest_tag).await?;
+          self.push(&tags).await?;
+        } else {
+          self.build_platforms(&repository, &tags, latest_tag).await?;
+        }
+      }
+      BuildBackend::Buildx => {
+        self
+          .build(&repository, &self.platforms.join(","), &tags, latest_tag)
+          .await?
+      }
     }
 
-    self.build(&repository).await?;
-    self.push(&repository).await?;
+    if let Some(scan_gate) = &self.scan_gate {
+      self.check_image_scan(scan_gate, &repository).await?;
+    }
 
     Ok(())
   }
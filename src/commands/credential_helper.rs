@@ -0,0 +1,84 @@
+use std::{collections::HashMap, io::Read};
+
+use aws_config::SdkConfig;
+use ecs_helpers::{
+  Command,
+  args::{CredentialHelperAction, CredentialHelperCommandArguments},
+  ecr::{self, EcrClient},
+  errors::EcsHelperVarietyError,
+};
+use serde_json::json;
+
+/// Implements the docker credential helper protocol, see
+/// https://github.com/docker/docker-credential-helpers#development
+pub struct CredentialHelperCommand {
+  sdk_config: SdkConfig,
+  action: CredentialHelperAction,
+}
+
+impl CredentialHelperCommand {
+  pub fn new(sdk_config: SdkConfig, args: CredentialHelperCommandArguments) -> Self {
+    Self {
+      sdk_config,
+      action: args.action,
+    }
+  }
+
+  fn read_stdin(&self) -> miette::Result<String, EcsHelperVarietyError> {
+    let mut input = String::new();
+
+    std::io::stdin()
+      .read_to_string(&mut input)
+      .map_err(EcsHelperVarietyError::ReedOutputError)?;
+
+    Ok(input.trim().to_string())
+  }
+
+  /// Prints credentials for the registry from stdin.
+  async fn get(&self) -> miette::Result<(), EcsHelperVarietyError> {
+    let server_url = self.read_stdin()?;
+    let registry = ecr::parse_registry(&server_url)
+      .ok_or_else(|| EcsHelperVarietyError::NotEcrRegistry(server_url.clone()))?;
+
+    let ecr_client = EcrClient::for_region(&self.sdk_config, registry.region.as_ref());
+    let authorization = ecr_client.get_authorization().await?;
+
+    let credentials = json!({
+      "ServerURL": server_url,
+      "Username": authorization.username,
+      "Secret": authorization.password,
+    });
+    println!("{credentials}");
+
+    Ok(())
+  }
+
+  /// Prints the registry of the current account.
+  async fn list(&self) -> miette::Result<(), EcsHelperVarietyError> {
+    let ecr_client = EcrClient::new(&self.sdk_config);
+    let authorization = ecr_client.get_authorization().await?;
+
+    let registries = HashMap::from([(authorization.registry, authorization.username)]);
+    println!("{}", json!(registries));
+
+    Ok(())
+  }
+}
+
+impl Command for CredentialHelperCommand {
+  fn name(&self) -> String {
+    "credential-helper".to_string()
+  }
+
+  async fn run(&self) -> miette::Result<(), EcsHelperVarietyError> {
+    match self.action {
+      CredentialHelperAction::Get => self.get().await,
+      CredentialHelperAction::List => self.list().await,
+      // tokens are requested on every get, so there is nothing to store or erase
+      CredentialHelperAction::Store | CredentialHelperAction::Erase => {
+        self.read_stdin()?;
+        Ok(())
+      }
+    }
+  }
+}
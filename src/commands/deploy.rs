@@ -1,9 +1,19 @@
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
-use aws_sdk_ecs::types::Service;
+use aws_sdk_ecs::types::{ContainerDefinition, Service};
 use ecs_helpers::{
-  Command, args::DeployCommandArguments, cluster_helpers, config::Config, ecr::EcrClient,
-  ecs::EcsClient, errors::EcsHelperVarietyError, service_helpers,
+  Command,
+  args::DeployCommandArguments,
+  cluster_helpers,
+  config::{Config, DEFAULT_TIMEOUT},
+  deployment_helpers::{self, DeploymentProgress, TrackedDeployment},
+  ecr::{self, EcrClient, EcrRegistry},
+  ecs::EcsClient,
+  errors::EcsHelperVarietyError,
+  image_overrides::ImageOverrides,
+  image_scan::{self, ScanGate},
+  service_helpers,
+  task_definition_diff::{self, DeploymentPlan},
 };
 
 const DEFAULT_STEP: u64 = 5;
@@ -14,7 +24,13 @@ pub struct DeployCommand {
   config: Config,
   timeout: u64,
   cluster: Option<String>,
-  service: Option<String>,
+  services: Option<Vec<String>>,
+  service_pattern: Option<String>,
+  rollback: bool,
+  dry_run: bool,
+  image_overrides: Vec<String>,
+  pin_digest: bool,
+  scan_gate: Option<ScanGate>,
 }
 
 impl DeployCommand {
@@ -26,10 +42,18 @@ impl DeployCommand {
     Self {
       ecs_client,
       ecr_client,
+      timeout: args.timeout.or(config.timeout).unwrap_or(DEFAULT_TIMEOUT),
+      cluster: args.cluster.or(config.cluster.to_owned()),
+      services: args
+        .service
+        .or(config.service.to_owned().map(|service| vec![service])),
+      service_pattern: args.service_pattern,
+      rollback: args.rollback,
+      dry_run: args.dry_run,
+      image_overrides: args.image_override.unwrap_or_default(),
+      pin_digest: args.pin_digest,
+      scan_gate: ScanGate::from_args(&args.image_scan),
       config,
-      timeout: args.timeout,
-      cluster: args.cluster,
-      service: args.service,
     }
   }
 
@@ -37,8 +61,12 @@ impl DeployCommand {
     &self,
     cluster_arn: &String,
     service_arn: &String,
+    deployment: &TrackedDeployment,
+    seen_event_ids: &mut HashSet<String>,
   ) -> miette::Result<Service, EcsHelperVarietyError> {
     let mut timeout = self.timeout;
+    let mut last_counts = String::new();
+    let service_name = service_helpers::service_name(service_arn);
 
     while timeout > 0 {
       let service = self
@@ -46,74 +74,244 @@ impl DeployCommand {
         .describe_service(cluster_arn, service_arn)
         .await?;
 
-      let deployment_count = service.deployments().len();
+      for event in deployment_helpers::new_service_events(&service, seen_event_ids) {
+        println!(
+          "[{service_name}] {}",
+          deployment_helpers::format_service_event(event)
+        );
+      }
+
+      if let Some(primary) = deployment_helpers::primary_deployment(&service) {
+        let counts = deployment_helpers::format_deployment_counts(primary);
 
-      if deployment_count == 1 {
-        log::info!("Service was deployed");
+        if counts != last_counts {
+          println!("[{service_name}] {counts}");
+          last_counts = counts;
+        }
+      }
+
+      let progress = deployment_helpers::deployment_progress(&service, deployment)?;
+
+      if progress == DeploymentProgress::Completed {
+        log::info!(
+          "Service {service_name} was deployed with task definition {}",
+          deployment.task_definition_arn
+        );
         return Ok(service);
       }
 
-      timeout -= DEFAULT_STEP;
+      timeout = timeout.saturating_sub(DEFAULT_STEP);
       tokio::time::sleep(Duration::from_secs(DEFAULT_STEP)).await;
     }
 
     Err(EcsHelperVarietyError::WaitTaskTimeoutError(self.timeout))
   }
-}
 
-impl Command for DeployCommand {
-  fn name(&self) -> String {
-    "deploy".to_string()
-  }
+  /// Points service back to the previous task definition and waits until it is stable.
+  async fn rollback(
+    &self,
+    cluster_arn: &String,
+    service_arn: &String,
+    previous_task_definition_arn: &String,
+    error: EcsHelperVarietyError,
+    seen_event_ids: &mut HashSet<String>,
+  ) -> EcsHelperVarietyError {
+    let service_name = service_helpers::service_name(service_arn);
 
-  async fn run(&self) -> Result<(), EcsHelperVarietyError> {
-    let cluster =
-      cluster_helpers::get_current_cluster(&self.ecs_client, &self.config, &self.cluster).await?;
-    let service =
-      service_helpers::get_current_service(&self.ecs_client, &self.config, &cluster, &self.service)
+    log::warn!(
+      "Deployment of {service_name} failed, rolling back to {previous_task_definition_arn}:\n{error}"
+    );
+
+    let rollback_result = async {
+      let service = self
+        .ecs_client
+        .update_service(cluster_arn, previous_task_definition_arn, service_arn)
         .await?;
+      let deployment = deployment_helpers::track_primary_deployment(&service)?;
+
+      self
+        .wait_for_deploy(cluster_arn, service_arn, &deployment, seen_event_ids)
+        .await
+    }
+    .await;
+
+    match rollback_result {
+      Ok(_) => {
+        log::info!(
+          "Rollback\nService {service_name} was rolled back to {previous_task_definition_arn}"
+        );
+
+        EcsHelperVarietyError::DeploymentRolledBack {
+          task_definition_arn: previous_task_definition_arn.to_owned(),
+          reason: error.to_string(),
+        }
+      }
+      Err(rollback_error) => EcsHelperVarietyError::RollbackFailed {
+        task_definition_arn: previous_task_definition_arn.to_owned(),
+        reason: error.to_string(),
+        rollback_reason: rollback_error.to_string(),
+      },
+    }
+  }
 
-    let service = self.ecs_client.describe_service(&cluster, &service).await?;
+  /// Prints what would be changed by the deployment of the service.
+  ///
+  /// Images which can't be found are reported in the plan, other errors fail the plan.
+  fn print_plan(
+    &self,
+    service: &Service,
+    task_definition_arn: &str,
+    container_definitions: &[ContainerDefinition],
+    container_definitions_to_ecr: Vec<
+      miette::Result<Option<ContainerDefinition>, EcsHelperVarietyError>,
+    >,
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let mut new_container_definitions = vec![];
+    let mut kept_containers = vec![];
+    let mut missing_images = vec![];
+
+    for (container_definition, container_definition_to_ecr) in container_definitions
+      .iter()
+      .zip(container_definitions_to_ecr)
+    {
+      let name = container_definition.name().unwrap_or_default().to_string();
+
+      match container_definition_to_ecr {
+        Ok(Some(new_container_definition)) => {
+          new_container_definitions.push(new_container_definition)
+        }
+        Ok(None) => kept_containers.push(name),
+        Err(error @ EcsHelperVarietyError::ImageNotFound { .. }) => {
+          missing_images.push((name, error.to_string()))
+        }
+        Err(error) => return Err(error),
+      }
+    }
+
+    let plan = DeploymentPlan {
+      cluster_arn: service.cluster_arn().unwrap_or_default().to_string(),
+      service_arn: service.service_arn().unwrap_or_default().to_string(),
+      task_definition_arn: task_definition_arn.to_string(),
+      container_diffs: task_definition_diff::diff_container_definitions(
+        container_definitions,
+        &new_container_definitions,
+      ),
+      kept_containers,
+      missing_images,
+    };
+
+    // services are planned concurrently, so the plan is printed at once
+    println!("{plan}\n");
+
+    Ok(())
+  }
+
+  /// Registers new task definition for the service, updates the service and waits for the deployment.
+  /// Returns ARN of the deployed task definition.
+  async fn deploy_service(
+    &self,
+    cluster: &String,
+    service: &String,
+    registry: &EcrRegistry,
+    image_overrides: &ImageOverrides,
+  ) -> miette::Result<String, EcsHelperVarietyError> {
+    let service = self.ecs_client.describe_service(cluster, service).await?;
+    // events which happened before the deployment are not interesting
+    let mut seen_event_ids = service
+      .events()
+      .iter()
+      .filter_map(|event| event.id().map(str::to_string))
+      .collect::<HashSet<_>>();
 
-    let service_task_definition = service.task_definition().unwrap().to_owned();
-    let service_task_definition = self
+    let previous_task_definition_arn = service.task_definition().unwrap().to_owned();
+    let (service_task_definition, service_task_definition_tags) = self
       .ecs_client
-      .describe_task_definition(&service_task_definition)
+      .describe_task_definition_with_tags(&previous_task_definition_arn)
       .await?;
 
-    let repositories = self.ecr_client.get_private_repositories().await?;
-
     let container_definitions = service_task_definition.container_definitions().to_vec();
+    image_overrides.check_containers(
+      &container_definitions
+        .iter()
+        .filter_map(|container_definition| container_definition.name())
+        .collect::<Vec<_>>(),
+    )?;
 
-    let container_definitions_to_ecr_tasks =
+    let container_definitions_to_ecr =
       futures::future::join_all(container_definitions.iter().map(|container_definition| {
-        let repositories = repositories.clone();
-
-        async move {
-          self
-            .ecr_client
-            .create_new_container_definition_from(
-              container_definition,
-              repositories,
-              &self.config.version,
-            )
-            .await
-        }
+        self.ecr_client.create_new_container_definition_from(
+          container_definition,
+          registry,
+          &self.config.version,
+          image_overrides,
+          self.pin_digest,
+        )
       }))
       .await;
-    let new_container_definitions = container_definitions_to_ecr_tasks
+
+    if self.dry_run {
+      self.print_plan(
+        &service,
+        &previous_task_definition_arn,
+        &container_definitions,
+        container_definitions_to_ecr,
+      )?;
+
+      return Ok(previous_task_definition_arn);
+    }
+
+    let container_definitions_to_ecr = container_definitions_to_ecr
+      .into_iter()
+      .collect::<miette::Result<Vec<_>, _>>()?;
+
+    let image_updates = container_definitions
       .iter()
-      .filter_map(
-        |container_definition_to_ecr| match container_definition_to_ecr {
-          Ok(container_definition_to_ecr) => Some(container_definition_to_ecr.to_owned()),
-          Err(_) => None,
-        },
-      )
+      .zip(&container_definitions_to_ecr)
+      .map(|(container_definition, container_definition_to_ecr)| {
+        ecr::format_image_update(container_definition, container_definition_to_ecr.as_ref())
+      })
+      .collect::<Vec<_>>();
+
+    let new_container_definitions = container_definitions
+      .iter()
+      .zip(container_definitions_to_ecr)
+      .map(|(container_definition, container_definition_to_ecr)| {
+        container_definition_to_ecr.unwrap_or_else(|| container_definition.to_owned())
+      })
       .collect::<Vec<_>>();
 
+    if let Some(scan_gate) = &self.scan_gate {
+      futures::future::try_join_all(
+        new_container_definitions
+          .iter()
+          .filter_map(|container_definition| container_definition.image())
+          .filter_map(|image| Some((ecr::private_repository_name(image, registry)?, image)))
+          .map(|(repository_name, image)| {
+            image_scan::wait_for_image_scans(
+              &self.ecr_client,
+              scan_gate,
+              repository_name,
+              ecr::image_identifier(image),
+              image,
+            )
+          }),
+      )
+      .await?;
+    }
+
+    let service_name = service_helpers::service_name(service.service_arn().unwrap_or_default());
+
+    for image_update in image_updates {
+      println!("[{service_name}] {image_update}");
+    }
+
     let new_service_task_definition = self
       .ecs_client
-      .register_task_definition_from(&service_task_definition, new_container_definitions)
+      .register_task_definition_from(
+        &service_task_definition,
+        &service_task_definition_tags,
+        new_container_definitions,
+      )
       .await?;
 
     log::info!("Register task definition\nTask definition was registered",);
@@ -134,11 +332,230 @@ impl Command for DeployCommand {
 
     let service_arn = service.service_arn().unwrap().to_owned();
     let cluster_arn = service.cluster_arn().unwrap().to_owned();
+    let deployment = deployment_helpers::track_primary_deployment(&service)?;
+
+    if let Err(error) = self
+      .wait_for_deploy(&cluster_arn, &service_arn, &deployment, &mut seen_event_ids)
+      .await
+    {
+      if !self.rollback {
+        return Err(error);
+      }
+
+      return Err(
+        self
+          .rollback(
+            &cluster_arn,
+            &service_arn,
+            &previous_task_definition_arn,
+            error,
+            &mut seen_event_ids,
+          )
+          .await,
+      );
+    }
+
+    Ok(service_task_definition_arn)
+  }
+}
+
+impl Command for DeployCommand {
+  fn name(&self) -> String {
+    "deploy".to_string()
+  }
+
+  async fn run(&self) -> Result<(), EcsHelperVarietyError> {
+    let image_overrides = ImageOverrides::parse(&self.image_overrides)?;
+    let cluster =
+      cluster_helpers::get_current_cluster(&self.ecs_client, &self.config, &self.cluster).await?;
+    let mut services = match (&self.service_pattern, &self.services) {
+      (Some(pattern), _) => {
+        service_helpers::get_services_by_pattern(&self.ecs_client, &self.config, &cluster, pattern)
+          .await?
+      }
+      (None, Some(services)) => {
+        futures::future::try_join_all(services.iter().map(|service| async {
+          service_helpers::get_current_service(
+            &self.ecs_client,
+            &self.config,
+            &cluster,
+            &Some(service.to_owned()),
+          )
+          .await
+        }))
+        .await?
+      }
+      (None, None) => vec![
+        service_helpers::get_current_service(&self.ecs_client, &self.config, &cluster, &None)
+          .await?,
+      ],
+    };
+
+    // several names could point to the same service
+    let mut unique_services = HashSet::new();
+    services.retain(|service| unique_services.insert(service.to_owned()));
 
-    self.wait_for_deploy(&cluster_arn, &service_arn).await?;
+    let registry = EcrRegistry {
+      account_id: self.config.aws_account_id.to_owned(),
+      region: Some(self.config.region.to_string()),
+    };
 
-    log::info!("Success\nApplication was successfully deployed");
+    let results = futures::future::join_all(
+      services
+        .iter()
+        .map(|service| self.deploy_service(&cluster, service, &registry, &image_overrides)),
+    )
+    .await;
+
+    // single service keeps the original error with its diagnostic
+    if results.len() == 1 {
+      let task_definition_arn = results.into_iter().next().unwrap()?;
+
+      if self.dry_run {
+        return Ok(());
+      }
+
+      println!(
+        "Service {} was deployed with {task_definition_arn}",
+        service_helpers::service_name(&services[0])
+      );
+      log::info!("Success\nApplication was successfully deployed");
+
+      return Ok(());
+    }
+
+    let (summary, result) = deployment_helpers::summarize_deployments(
+      services
+        .iter()
+        .map(|service| service_helpers::service_name(service))
+        .zip(results)
+        .collect(),
+      self.dry_run,
+    );
+
+    println!("{}", summary.join("\n"));
+    result?;
+
+    if !self.dry_run {
+      log::info!("Success\nApplication was successfully deployed");
+    }
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aws_config::{BehaviorVersion, Region, SdkConfig};
+  use aws_sdk_ecs::config::{Credentials, SharedCredentialsProvider};
+  use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
+  use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+  use aws_smithy_types::body::SdkBody;
+  use ecs_helpers::image_tags::TagContext;
+
+  const CLUSTER_ARN: &str = "arn:aws:ecs:us-east-1:123456789012:cluster/default";
+  const SERVICE_ARN: &str = "arn:aws:ecs:us-east-1:123456789012:service/default/web";
+  const PREVIOUS_TASK_DEFINITION_ARN: &str =
+    "arn:aws:ecs:us-east-1:123456789012:task-definition/web:1";
+
+  fn deploy_command(http_client: StaticReplayClient) -> DeployCommand {
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(SharedCredentialsProvider::new(
+        Credentials::for_tests_with_session_token(),
+      ))
+      .http_client(http_client)
+      .build();
+
+    DeployCommand {
+      ecs_client: EcsClient::new(&sdk_config),
+      ecr_client: EcrClient::new(&sdk_config),
+      timeout: DEFAULT_TIMEOUT,
+      cluster: None,
+      services: None,
+      service_pattern: None,
+      rollback: true,
+      dry_run: false,
+      image_overrides: vec![],
+      pin_digest: false,
+      scan_gate: None,
+      config: Config {
+        environment: "production".to_string(),
+        version: "1234567890".to_string(),
+        project: "shop".to_string(),
+        application: "web".to_string(),
+        region: Region::new("us-east-1"),
+        sdk_config,
+        aws_account_id: "123456789012".to_string(),
+        cluster: None,
+        service: None,
+        timeout: None,
+        build_args: None,
+        tag_context: TagContext::default(),
+      },
+    }
+  }
+
+  fn service_response(service: &str) -> ReplayEvent {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(service.to_string()))
+      .unwrap();
+
+    ReplayEvent::new(request, response)
+  }
+
+  #[tokio::test]
+  async fn test_rollback_updates_service_with_previous_task_definition() {
+    let deployment = format!(
+      r#"{{
+        "id": "ecs-svc/2",
+        "status": "PRIMARY",
+        "taskDefinition": "{PREVIOUS_TASK_DEFINITION_ARN}",
+        "rolloutState": "COMPLETED"
+      }}"#
+    );
+    let http_client = StaticReplayClient::new(vec![
+      service_response(&format!(
+        r#"{{"service": {{"serviceArn": "{SERVICE_ARN}", "deployments": [{deployment}]}}}}"#
+      )),
+      service_response(&format!(
+        r#"{{"services": [{{"serviceArn": "{SERVICE_ARN}", "deployments": [{deployment}]}}]}}"#
+      )),
+    ]);
+    let command = deploy_command(http_client.clone());
+
+    let error = command
+      .rollback(
+        &CLUSTER_ARN.to_string(),
+        &SERVICE_ARN.to_string(),
+        &PREVIOUS_TASK_DEFINITION_ARN.to_string(),
+        EcsHelperVarietyError::WaitTaskTimeoutError(DEFAULT_TIMEOUT),
+        &mut HashSet::new(),
+      )
+      .await;
+
+    assert!(matches!(
+      error,
+      EcsHelperVarietyError::DeploymentRolledBack { ref task_definition_arn, .. }
+        if task_definition_arn == PREVIOUS_TASK_DEFINITION_ARN
+    ));
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    assert_eq!(
+      requests[0].headers().get("x-amz-target"),
+      Some("AmazonEC2ContainerServiceV20141113.UpdateService")
+    );
+    let update_service_request: serde_json::Value =
+      serde_json::from_slice(requests[0].body().bytes().unwrap()).unwrap();
+    assert_eq!(
+      update_service_request["taskDefinition"],
+      PREVIOUS_TASK_DEFINITION_ARN
+    );
+    assert_eq!(update_service_request["service"], SERVICE_ARN);
+    assert_eq!(update_service_request["cluster"], CLUSTER_ARN);
+  }
+}
@@ -26,12 +26,12 @@ impl ExecCommand {
     Self {
       ecs_client,
       ssm_client,
-      config,
-      cluster: args.cluster,
-      service: args.service,
+      cluster: args.cluster.or(config.cluster.to_owned()),
+      service: args.service.or(config.service.to_owned()),
       task: args.task,
       command: args.command,
       container: args.container,
+      config,
     }
   }
 }
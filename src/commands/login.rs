@@ -1,14 +1,57 @@
+use std::path::PathBuf;
+
 use ecs_helpers::{
-  Command, args::LoginCommandArguments, auth, config::Config, errors::EcsHelperVarietyError,
+  Command,
+  args::{ContainerEngine, LoginCommandArguments},
+  auth,
+  config::Config,
+  container_engine,
+  ecr::EcrRegistry,
+  errors::EcsHelperVarietyError,
 };
 
 pub struct LoginCommand {
   config: Config,
+  write_docker_config: bool,
+  docker_config_file: Option<PathBuf>,
+  registries: Vec<String>,
+  public: bool,
+  engine: ContainerEngine,
 }
 
 impl LoginCommand {
-  pub fn new(config: Config, _: LoginCommandArguments) -> Self {
-    Self { config }
+  pub fn new(config: Config, args: LoginCommandArguments) -> Self {
+    Self {
+      config,
+      write_docker_config: args.write_docker_config || args.docker_config_file.is_some(),
+      docker_config_file: args.docker_config_file,
+      registries: args.registry.unwrap_or_default(),
+      public: args.public,
+      engine: container_engine::resolve(args.engine),
+    }
+  }
+
+  /// Returns the registry of the config followed by the additional registries,
+  /// or nothing when only the default registry is needed.
+  fn registries(&self) -> miette::Result<Vec<EcrRegistry>, EcsHelperVarietyError> {
+    if self.registries.is_empty() {
+      return Ok(vec![]);
+    }
+
+    let mut registries = vec![EcrRegistry {
+      account_id: self.config.aws_account_id.to_owned(),
+      region: Some(self.config.region.to_string()),
+    }];
+
+    for registry in &self.registries {
+      let registry = EcrRegistry::parse(registry)?;
+
+      if !registries.contains(&registry) {
+        registries.push(registry);
+      }
+    }
+
+    Ok(registries)
   }
 }
 
@@ -25,7 +68,34 @@ impl Command for LoginCommand {
       ..
     } = &self.config;
 
-    let auth_output = auth::login_to_ecr(sdk_config, region, aws_account_id).await?;
+    let registries = self.registries()?;
+
+    if self.write_docker_config {
+      auth::write_ecr_docker_config(sdk_config, &registries, self.docker_config_file.as_deref())
+        .await?;
+
+      if self.public {
+        auth::write_public_ecr_docker_config(sdk_config, self.docker_config_file.as_deref())
+          .await?;
+      }
+
+      log::info!("Login succeeded!");
+
+      return Ok(());
+    }
+
+    if self.public {
+      auth::login_to_public_ecr(self.engine, sdk_config).await?;
+    }
+
+    if !registries.is_empty() {
+      auth::login_to_registries(self.engine, sdk_config, &registries).await?;
+      log::info!("Login succeeded!");
+
+      return Ok(());
+    }
+
+    let auth_output = auth::login_to_ecr(self.engine, sdk_config, region, aws_account_id).await?;
 
     if auth_output.status.success() {
       log::info!("Login succeeded!");
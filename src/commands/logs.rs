@@ -0,0 +1,205 @@
+use std::{
+  collections::HashMap,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use aws_sdk_cloudwatchlogs::types::FilteredLogEvent;
+use ecs_helpers::{
+  Command,
+  args::LogsCommandArguments,
+  cloudwatch_logs::{self, CloudWatchLogsClient},
+  cluster_helpers,
+  config::Config,
+  ecs::EcsClient,
+  errors::EcsHelperVarietyError,
+  service_helpers,
+};
+
+const DEFAULT_STEP: u64 = 5;
+
+/// Log stream of a single container of a running task.
+struct LogSource {
+  log_group: String,
+  region: Option<String>,
+  log_stream: String,
+  label: String,
+}
+
+pub struct LogsCommand {
+  ecs_client: EcsClient,
+  cluster: Option<String>,
+  service: Option<String>,
+  container: Option<String>,
+  since: String,
+  follow: bool,
+  filter_pattern: Option<String>,
+  config: Config,
+}
+
+impl LogsCommand {
+  pub fn new(config: Config, args: LogsCommandArguments) -> Self {
+    let ecs_client = EcsClient::new(&config.sdk_config);
+
+    Self {
+      ecs_client,
+      cluster: args.cluster.or(config.cluster.to_owned()),
+      service: args.service.or(config.service.to_owned()),
+      container: args.container,
+      since: args.since,
+      follow: args.follow,
+      filter_pattern: args.filter_pattern,
+      config,
+    }
+  }
+
+  async fn get_log_sources(
+    &self,
+    cluster: &String,
+    service: &String,
+  ) -> miette::Result<Vec<LogSource>, EcsHelperVarietyError> {
+    let service = self.ecs_client.describe_service(cluster, service).await?;
+    let service_arn = service.service_arn().unwrap().to_owned();
+    let task_definition = service.task_definition().unwrap().to_owned();
+    let task_definition = self
+      .ecs_client
+      .describe_task_definition(&task_definition)
+      .await?;
+
+    let awslogs_configurations = cloudwatch_logs::awslogs_configurations(
+      task_definition.container_definitions(),
+      self.container.as_deref(),
+    );
+
+    if awslogs_configurations.is_empty() {
+      return Err(EcsHelperVarietyError::NoLogConfiguration);
+    }
+
+    let tasks = self.ecs_client.get_tasks(cluster, &service_arn).await?;
+
+    let log_sources = tasks
+      .iter()
+      .flat_map(|task_arn| {
+        awslogs_configurations
+          .iter()
+          .filter_map(move |(container_name, configuration)| {
+            let log_stream = configuration.log_stream_name(container_name, task_arn)?;
+
+            Some(LogSource {
+              log_group: configuration.group.to_owned(),
+              region: configuration.region.to_owned(),
+              log_stream,
+              label: format!("{}/{container_name}", cloudwatch_logs::task_id(task_arn)),
+            })
+          })
+      })
+      .collect();
+
+    Ok(log_sources)
+  }
+
+  /// Fetches events of all sources and returns them ordered by timestamp with the source label.
+  async fn get_log_events(
+    &self,
+    log_sources: &[LogSource],
+    start_time: i64,
+  ) -> miette::Result<Vec<(String, FilteredLogEvent)>, EcsHelperVarietyError> {
+    let mut log_groups: HashMap<(&String, &Option<String>), Vec<&LogSource>> = HashMap::new();
+
+    for log_source in log_sources {
+      log_groups
+        .entry((&log_source.log_group, &log_source.region))
+        .or_default()
+        .push(log_source);
+    }
+
+    let mut events = vec![];
+
+    for ((log_group, region), log_sources) in log_groups {
+      let client = CloudWatchLogsClient::for_region(&self.config.sdk_config, region.as_ref());
+      let log_streams = log_sources
+        .iter()
+        .map(|log_source| log_source.log_stream.to_owned())
+        .collect::<Vec<_>>();
+
+      let group_events = client
+        .filter_log_events(
+          log_group,
+          &log_streams,
+          start_time,
+          self.filter_pattern.as_ref(),
+        )
+        .await?;
+
+      events.extend(group_events.into_iter().map(|event| {
+        let label = log_sources
+          .iter()
+          .find(|log_source| Some(log_source.log_stream.as_str()) == event.log_stream_name())
+          .map(|log_source| log_source.label.to_owned())
+          .unwrap_or_default();
+
+        (label, event)
+      }));
+    }
+
+    events.sort_by_key(|(_, event)| event.timestamp());
+
+    Ok(events)
+  }
+}
+
+impl Command for LogsCommand {
+  fn name(&self) -> String {
+    "logs".to_string()
+  }
+
+  async fn run(&self) -> miette::Result<(), EcsHelperVarietyError> {
+    let since = cloudwatch_logs::parse_duration(&self.since)?;
+
+    let cluster =
+      cluster_helpers::get_current_cluster(&self.ecs_client, &self.config, &self.cluster).await?;
+    let service =
+      service_helpers::get_current_service(&self.ecs_client, &self.config, &cluster, &self.service)
+        .await?;
+
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default();
+    let mut start_time = now.saturating_sub(since).as_millis() as i64;
+    // event id -> timestamp of the already printed events, needed because followed queries overlap
+    let mut printed_events: HashMap<String, i64> = HashMap::new();
+
+    loop {
+      let log_sources = self.get_log_sources(&cluster, &service).await?;
+
+      if log_sources.is_empty() {
+        log::warn!("No running tasks found for {service}");
+      }
+
+      let events = self.get_log_events(&log_sources, start_time).await?;
+
+      for (label, event) in events {
+        let event_id = event.event_id().unwrap_or_default().to_string();
+        let timestamp = event.timestamp().unwrap_or_default();
+
+        if printed_events.insert(event_id, timestamp).is_none() {
+          println!(
+            "[{label}] {}",
+            event.message().unwrap_or_default().trim_end()
+          );
+        }
+      }
+
+      if !self.follow {
+        return Ok(());
+      }
+
+      let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+      start_time = cloudwatch_logs::follow_start_time(start_time, now);
+      printed_events.retain(|_, timestamp| *timestamp >= start_time);
+
+      tokio::time::sleep(Duration::from_secs(DEFAULT_STEP)).await;
+    }
+  }
+}
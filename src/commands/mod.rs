@@ -1,15 +1,19 @@
 mod build_and_push;
+mod credential_helper;
 mod deploy;
 mod exec;
 mod export_env_secrets;
 mod export_images;
 mod login;
+mod logs;
 mod run_command;
 
 pub use build_and_push::BuildAndPushCommand;
+pub use credential_helper::CredentialHelperCommand;
 pub use deploy::DeployCommand;
 pub use exec::ExecCommand;
 pub use export_env_secrets::ExportEnvSecretsCommand;
 pub use export_images::ExportImagesCommand;
 pub use login::LoginCommand;
+pub use logs::LogsCommand;
 pub use run_command::RunCommandCommand;
@@ -1,9 +1,17 @@
 use std::time::Duration;
 
-use aws_sdk_ecs::types::{ContainerDefinition, Task};
+use aws_sdk_ecs::types::Task;
 use ecs_helpers::{
-  Command, args::RunCommandArguments, cluster_helpers, config::Config, ecr::EcrClient,
-  ecs::EcsClient, errors::EcsHelperVarietyError, service_helpers,
+  Command,
+  args::RunCommandArguments,
+  cloudwatch_logs::{AwsLogsConfiguration, CloudWatchLogsClient, LogStreamTail},
+  cluster_helpers,
+  config::{Config, DEFAULT_TIMEOUT},
+  ecr::{self, EcrClient, EcrRegistry},
+  ecs::EcsClient,
+  errors::EcsHelperVarietyError,
+  image_overrides::ImageOverrides,
+  service_helpers, task_helpers,
 };
 
 const DEFAULT_STEP: u64 = 5;
@@ -19,6 +27,8 @@ pub struct RunCommandCommand {
   service: Option<String>,
   name: Option<String>,
   container: Option<String>,
+  image_overrides: Vec<String>,
+  pin_digest: bool,
 }
 
 impl RunCommandCommand {
@@ -30,13 +40,37 @@ impl RunCommandCommand {
     Self {
       ecs_client,
       ecr_client,
-      config,
       name: args.name,
-      timeout: args.timeout,
-      cluster: args.cluster,
-      service: args.service,
+      timeout: args.timeout.or(config.timeout).unwrap_or(DEFAULT_TIMEOUT),
+      cluster: args.cluster.or(config.cluster.to_owned()),
+      service: args.service.or(config.service.to_owned()),
       command: args.command,
       container: args.container,
+      image_overrides: args.image_override.unwrap_or_default(),
+      pin_digest: args.pin_digest,
+      config,
+    }
+  }
+
+  /// Prints new lines of the task logs, stops following the stream if logs are not readable.
+  async fn print_task_logs(&self, log_stream_tail: &mut Option<LogStreamTail>) {
+    let Some(tail) = log_stream_tail else {
+      return;
+    };
+
+    match tail.next_events().await {
+      Ok(events) => {
+        for event in events {
+          println!("{}", event.message().unwrap_or_default());
+        }
+      }
+      Err(error) => {
+        log::warn!(
+          "Failed to read logs from {}, stop following:\n{error}",
+          tail.log_stream()
+        );
+        *log_stream_tail = None;
+      }
     }
   }
 
@@ -44,15 +78,20 @@ impl RunCommandCommand {
     &self,
     task_arn: &String,
     cluster_arn: &String,
+    mut log_stream_tail: Option<LogStreamTail>,
   ) -> miette::Result<Task, EcsHelperVarietyError> {
     let mut timeout = self.timeout;
 
     while timeout > 0 {
-      let task = self.ecs_client.describe_task(task_arn, cluster_arn).await?;
+      self.print_task_logs(&mut log_stream_tail).await;
 
+      let task = self.ecs_client.describe_task(task_arn, cluster_arn).await?;
       let last_status = task.last_status().unwrap();
 
       if last_status == STOPPED_STATUS {
+        // the last lines could be written between the previous read and the stop
+        self.print_task_logs(&mut log_stream_tail).await;
+
         let container = task.containers().first().unwrap();
 
         match container.exit_code().unwrap() {
@@ -70,53 +109,12 @@ impl RunCommandCommand {
         }
       }
 
-      timeout -= DEFAULT_STEP;
+      timeout = timeout.saturating_sub(DEFAULT_STEP);
       tokio::time::sleep(Duration::from_secs(DEFAULT_STEP)).await;
     }
 
     Err(EcsHelperVarietyError::WaitTaskTimeoutError(self.timeout))
   }
-
-  fn build_custom_task_definition(
-    &self,
-    from_container_definition: &ContainerDefinition,
-  ) -> ContainerDefinition {
-    let mut new_container_definition = from_container_definition.clone();
-
-    let container_name = from_container_definition.name().unwrap();
-    let name = match self.name {
-      Some(ref name) => format!("{container_name}-{name}"),
-      None => container_name.to_string(),
-    };
-
-    let new_log_configuration = new_container_definition
-      .log_configuration()
-      .unwrap()
-      .to_owned();
-    let mut new_options = new_log_configuration.options().unwrap().to_owned();
-    let new_log_configuration_prefix = format!(
-      "{}-{name}",
-      new_log_configuration
-        .options()
-        .unwrap()
-        .get("awslogs-stream-prefix")
-        .unwrap()
-    );
-    new_options.insert(
-      "awslogs-stream-prefix".to_string(),
-      new_log_configuration_prefix,
-    );
-
-    new_container_definition.log_configuration = Some(new_log_configuration);
-    new_container_definition.name = Some(name);
-    new_container_definition.command = Some(vec![
-      "bash".to_string(),
-      "-c".to_string(),
-      self.command.clone(),
-    ]);
-
-    new_container_definition
-  }
 }
 
 impl Command for RunCommandCommand {
@@ -125,6 +123,8 @@ impl Command for RunCommandCommand {
   }
 
   async fn run(&self) -> miette::Result<(), EcsHelperVarietyError> {
+    let image_overrides = ImageOverrides::parse(&self.image_overrides)?;
+
     let cluster =
       cluster_helpers::get_current_cluster(&self.ecs_client, &self.config, &self.cluster).await?;
 
@@ -136,43 +136,25 @@ impl Command for RunCommandCommand {
 
     let service_task_definition = service.task_definition().unwrap().to_owned();
 
-    let service_task_definition = self
+    let (service_task_definition, service_task_definition_tags) = self
       .ecs_client
-      .describe_task_definition(&service_task_definition)
+      .describe_task_definition_with_tags(&service_task_definition)
       .await?;
 
-    let repositories = self.ecr_client.get_private_repositories().await?;
-
-    let container_definitions = service_task_definition.container_definitions().to_vec();
-
-    let container_definitions_to_ecr_tasks =
-      futures::future::join_all(container_definitions.iter().map(|container_definition| {
-        let repositories = repositories.clone();
+    let registry = EcrRegistry {
+      account_id: self.config.aws_account_id.to_owned(),
+      region: Some(self.config.region.to_string()),
+    };
 
-        async move {
-          self
-            .ecr_client
-            .create_new_container_definition_from(
-              container_definition,
-              repositories,
-              &self.config.version,
-            )
-            .await
-        }
-      }))
-      .await;
+    let container_definitions = service_task_definition.container_definitions();
+    image_overrides.check_containers(
+      &container_definitions
+        .iter()
+        .filter_map(|container_definition| container_definition.name())
+        .collect::<Vec<_>>(),
+    )?;
 
-    let container_definitions_to_ecr = container_definitions_to_ecr_tasks
-      .iter()
-      .filter_map(
-        |container_definition_to_ecr| match container_definition_to_ecr {
-          Ok(container_definition_to_ecr) => Some(container_definition_to_ecr.to_owned()),
-          Err(_) => None,
-        },
-      )
-      .collect::<Vec<_>>();
-
-    let new_container_definition = container_definitions_to_ecr
+    let container_definition = container_definitions
       .iter()
       .find(|container_definition| {
         let container_name = match container_definition.name() {
@@ -185,18 +167,44 @@ impl Command for RunCommandCommand {
           None => true,
         }
       })
-      .unwrap_or(
-        container_definitions_to_ecr
-          .first()
-          .ok_or(EcsHelperVarietyError::CannotFindContainerDefinition)?,
+      .or(container_definitions.first())
+      .ok_or(EcsHelperVarietyError::CannotFindContainerDefinition)?;
+
+    let container_definition_to_ecr = self
+      .ecr_client
+      .create_new_container_definition_from(
+        container_definition,
+        &registry,
+        &self.config.version,
+        &image_overrides,
+        self.pin_digest,
       )
-      .to_owned();
+      .await?;
 
-    let new_container_definition = self.build_custom_task_definition(&new_container_definition);
+    println!(
+      "{}",
+      ecr::format_image_update(container_definition, container_definition_to_ecr.as_ref())
+    );
+
+    let new_container_definition =
+      container_definition_to_ecr.unwrap_or_else(|| container_definition.to_owned());
+
+    let new_container_definition = task_helpers::build_custom_container_definition(
+      &new_container_definition,
+      self.name.as_deref(),
+      &self.command,
+    );
+    let awslogs_configuration =
+      AwsLogsConfiguration::from_container_definition(&new_container_definition);
+    let container_name = new_container_definition.name().unwrap().to_owned();
 
     let new_service_task_definition = self
       .ecs_client
-      .register_task_definition_from(&service_task_definition, vec![new_container_definition])
+      .register_task_definition_from(
+        &service_task_definition,
+        &service_task_definition_tags,
+        vec![new_container_definition],
+      )
       .await?;
 
     let task_definition_arn = new_service_task_definition
@@ -219,8 +227,109 @@ impl Command for RunCommandCommand {
 
     let task_arn = task.task_arn().unwrap().to_owned();
 
-    self.wait_for_task(&task_arn, &cluster).await?;
+    let log_stream_tail = awslogs_configuration.and_then(|awslogs_configuration| {
+      let log_stream = awslogs_configuration.log_stream_name(&container_name, &task_arn)?;
+      log::info!(
+        "Follow logs: {} {}",
+        awslogs_configuration.group,
+        log_stream
+      );
+
+      let client = CloudWatchLogsClient::for_region(
+        &self.config.sdk_config,
+        awslogs_configuration.region.as_ref(),
+      );
+
+      Some(LogStreamTail::new(
+        client,
+        awslogs_configuration.group,
+        log_stream,
+      ))
+    });
+
+    self
+      .wait_for_task(&task_arn, &cluster, log_stream_tail)
+      .await?;
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aws_config::{BehaviorVersion, Region, SdkConfig};
+  use aws_sdk_ecs::config::{Credentials, SharedCredentialsProvider};
+  use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
+  use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+  use aws_smithy_types::body::SdkBody;
+  use ecs_helpers::image_tags::TagContext;
+
+  const CLUSTER_ARN: &str = "arn:aws:ecs:us-east-1:123456789012:cluster/default";
+  const TASK_ARN: &str = "arn:aws:ecs:us-east-1:123456789012:task/default/0123456789abcdef";
+
+  fn run_command_command(http_client: StaticReplayClient, timeout: u64) -> RunCommandCommand {
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(SharedCredentialsProvider::new(
+        Credentials::for_tests_with_session_token(),
+      ))
+      .http_client(http_client)
+      .build();
+
+    RunCommandCommand {
+      ecs_client: EcsClient::new(&sdk_config),
+      ecr_client: EcrClient::new(&sdk_config),
+      command: "rails db:migrate".to_string(),
+      timeout,
+      cluster: None,
+      service: None,
+      name: None,
+      container: None,
+      image_overrides: vec![],
+      pin_digest: false,
+      config: Config {
+        environment: "production".to_string(),
+        version: "1234567890".to_string(),
+        project: "shop".to_string(),
+        application: "web".to_string(),
+        region: Region::new("us-east-1"),
+        sdk_config,
+        aws_account_id: "123456789012".to_string(),
+        cluster: None,
+        service: None,
+        timeout: None,
+        build_args: None,
+        tag_context: TagContext::default(),
+      },
+    }
+  }
+
+  fn running_task() -> ReplayEvent {
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(format!(
+        r#"{{"tasks": [{{"taskArn": "{TASK_ARN}", "lastStatus": "RUNNING"}}]}}"#
+      )))
+      .unwrap();
+
+    ReplayEvent::new(HttpRequest::new(SdkBody::from("")), response)
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn test_wait_for_task_with_timeout_not_multiple_of_step() {
+    let http_client = StaticReplayClient::new(vec![running_task(), running_task()]);
+    let command = run_command_command(http_client.clone(), 7);
+
+    let result = command
+      .wait_for_task(&TASK_ARN.to_string(), &CLUSTER_ARN.to_string(), None)
+      .await;
+
+    assert!(matches!(
+      result,
+      Err(EcsHelperVarietyError::WaitTaskTimeoutError(7))
+    ));
+    assert_eq!(http_client.actual_requests().count(), 2);
+  }
+}
@@ -1,8 +1,18 @@
-use crate::{args::CommandArguments, errors::EcsHelperVarietyError};
-use aws_config::{Region, SdkConfig};
-use git2::Repository;
-
-const DEFAULT_REGION: &str = "us-east-1";
+use std::time::Duration;
+
+use crate::{
+  args::{AssumeRoleArguments, CommandArguments},
+  branch_mapping::{BranchMapping, BranchRule},
+  config_file::ConfigFile,
+  errors::EcsHelperVarietyError,
+  image_tags::{self, TagContext},
+};
+use aws_config::{BehaviorVersion, Region, SdkConfig, sts::AssumeRoleProvider};
+use aws_sdk_sts::config::SharedCredentialsProvider;
+use git2::{DescribeOptions, Repository};
+
+const DEFAULT_ROLE_SESSION_NAME: &str = "ecs-helpers";
+pub const DEFAULT_TIMEOUT: u64 = 600;
 
 #[derive(Debug)]
 pub struct Config {
@@ -13,32 +23,77 @@ pub struct Config {
   pub region: Region,
   pub sdk_config: SdkConfig,
   pub aws_account_id: String,
+  pub cluster: Option<String>,
+  pub service: Option<String>,
+  pub timeout: Option<u64>,
+  pub build_args: Option<Vec<String>>,
+  pub tag_context: TagContext,
 }
 
 impl Config {
   pub async fn new(args: &CommandArguments) -> miette::Result<Self, EcsHelperVarietyError> {
-    let sdk_config = aws_config::load_from_env().await;
+    let config_file = ConfigFile::load(args.config_file.as_deref())?;
+
+    let project = args
+      .project
+      .to_owned()
+      .or(config_file.project.to_owned())
+      .ok_or(EcsHelperVarietyError::MissingRequiredOption(
+        "project".to_string(),
+      ))?;
+    let application = args
+      .application
+      .to_owned()
+      .or(config_file.application.to_owned())
+      .ok_or(EcsHelperVarietyError::MissingRequiredOption(
+        "application".to_string(),
+      ))?;
+    let sdk_config = Config::load_aws_config(args).await?;
 
     let commit_sha = Config::extract_commit_sha()?;
     let environment = match args.environment.to_owned() {
       Some(environment) => environment,
-      None => Config::extract_environment()?,
+      None => {
+        let branch_mapping = Config::build_branch_mapping(args, &config_file)?;
+        Config::extract_environment(&branch_mapping)?
+      }
     };
-
-    let version = args.version.to_owned().unwrap_or(Config::extract_version(
-      args.use_image_tag_env_prefix,
+    let settings = config_file.settings_for(&environment);
+
+    let use_image_tag_env_prefix = args
+      .use_image_tag_env_prefix
+      .or(config_file.use_image_tag_env_prefix)
+      .unwrap_or(false);
+    let tag_context = TagContext {
+      environment: environment.to_owned(),
       commit_sha,
-      &environment,
-    ));
-    let aws_account_id = args
-      .aws_account_id
+      branch: std::env::var("CI_COMMIT_BRANCH")
+        .ok()
+        .or_else(|| Config::extract_branch_name().ok()),
+      semver: Config::extract_semver(),
+      date: image_tags::format_date(aws_smithy_types::DateTime::from(
+        std::time::SystemTime::now(),
+      )),
+    };
+    let version_template = args
+      .version_template
       .to_owned()
-      .unwrap_or(Config::extract_aws_account_id(&sdk_config).await);
-    let project = args.project.to_owned();
-    let application = args.application.to_owned();
+      .or(config_file.version_template.to_owned());
+    let version = match args.version.to_owned() {
+      Some(version) => version,
+      None => Config::extract_version(
+        use_image_tag_env_prefix,
+        version_template.as_deref(),
+        &tag_context,
+      )?,
+    };
+    let aws_account_id = match args.aws_account_id.to_owned().or(settings.aws_account_id) {
+      Some(aws_account_id) => aws_account_id,
+      None => Config::extract_aws_account_id(&sdk_config).await,
+    };
     let region = sdk_config
       .region()
-      .unwrap_or(&Region::new(DEFAULT_REGION))
+      .ok_or(EcsHelperVarietyError::GetRegionError)?
       .to_owned();
 
     Ok(Self {
@@ -49,9 +104,96 @@ impl Config {
       environment,
       project,
       aws_account_id,
+      cluster: settings.cluster,
+      service: settings.service,
+      timeout: settings.timeout,
+      build_args: settings.build_args,
+      tag_context,
     })
   }
 
+  fn build_branch_mapping(
+    args: &CommandArguments,
+    config_file: &ConfigFile,
+  ) -> miette::Result<BranchMapping, EcsHelperVarietyError> {
+    let cli_rules = args
+      .branch_environment
+      .iter()
+      .flatten()
+      .map(|rule| BranchRule::parse(rule));
+    let config_file_rules = config_file
+      .branches
+      .iter()
+      .map(|rule| BranchRule::new(&rule.pattern, &rule.environment));
+
+    let rules = cli_rules
+      .chain(config_file_rules)
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(BranchMapping::new(rules))
+  }
+
+  /// Loads the SDK config for the profile, region and role flags, it needs no project settings.
+  pub async fn load_aws_config(
+    args: &CommandArguments,
+  ) -> miette::Result<SdkConfig, EcsHelperVarietyError> {
+    let sdk_config =
+      Config::assume_role(Config::load_sdk_config(args).await, &args.assume_role).await;
+
+    if sdk_config.region().is_none() {
+      return Err(EcsHelperVarietyError::GetRegionError);
+    }
+
+    Ok(sdk_config)
+  }
+
+  /// Loads the SDK config from the environment, the profile and region flags take precedence.
+  async fn load_sdk_config(args: &CommandArguments) -> SdkConfig {
+    let mut loader = aws_config::defaults(BehaviorVersion::latest());
+
+    if let Some(profile) = &args.profile {
+      loader = loader.profile_name(profile);
+    }
+
+    if let Some(region) = &args.region {
+      loader = loader.region(Region::new(region.to_owned()));
+    }
+
+    loader.load().await
+  }
+
+  /// Replaces credentials of the SDK config with the assumed role ones when the role is set.
+  async fn assume_role(sdk_config: SdkConfig, args: &AssumeRoleArguments) -> SdkConfig {
+    let Some(role_arn) = &args.role_arn else {
+      return sdk_config;
+    };
+
+    let mut provider = AssumeRoleProvider::builder(role_arn)
+      .session_name(
+        args
+          .role_session_name
+          .as_deref()
+          .unwrap_or(DEFAULT_ROLE_SESSION_NAME),
+      )
+      .configure(&sdk_config);
+
+    if let Some(external_id) = &args.external_id {
+      provider = provider.external_id(external_id);
+    }
+
+    if let Some(duration) = args.duration {
+      provider = provider.session_length(Duration::from_secs(duration));
+    }
+
+    let provider = provider.build().await;
+    log::debug!("Assuming role {role_arn}");
+
+    sdk_config
+      .into_builder()
+      .credentials_provider(SharedCredentialsProvider::new(provider))
+      .build()
+  }
+
   async fn extract_aws_account_id(sdk_config: &SdkConfig) -> String {
     let sts_client = aws_sdk_sts::Client::new(sdk_config);
     let caller_identity = sts_client.get_caller_identity().send().await;
@@ -73,16 +215,37 @@ impl Config {
     }
   }
 
+  /// Renders the version tag, build_and_push pushes and deploy looks up the same tag.
   fn extract_version(
     use_image_tag_env_prefix: bool,
-    commit_sha: String,
-    environment: &String,
-  ) -> String {
-    if use_image_tag_env_prefix {
-      format!("{}-{}", environment, commit_sha)
+    version_template: Option<&str>,
+    tag_context: &TagContext,
+  ) -> miette::Result<String, EcsHelperVarietyError> {
+    let default_template = if use_image_tag_env_prefix {
+      "{environment}-{sha}"
     } else {
-      commit_sha
-    }
+      "{sha}"
+    };
+
+    image_tags::render_version(version_template.unwrap_or(default_template), tag_context)
+  }
+
+  /// Returns semver of the release tag, `CI_COMMIT_TAG` or a git tag pointing to `HEAD`.
+  fn extract_semver() -> Option<String> {
+    let tag = std::env::var("CI_COMMIT_TAG").ok().or_else(|| {
+      let repo = Repository::open(".").ok()?;
+      let describe = repo
+        .describe(
+          DescribeOptions::new()
+            .describe_tags()
+            .max_candidates_tags(0),
+        )
+        .ok()?;
+
+      describe.format(None).ok()
+    })?;
+
+    image_tags::parse_semver(&tag)
   }
 
   fn extract_commit_sha() -> miette::Result<String, EcsHelperVarietyError> {
@@ -116,42 +279,266 @@ impl Config {
     Ok(branch.to_string())
   }
 
-  fn extract_environment() -> miette::Result<String, EcsHelperVarietyError> {
+  fn extract_environment(
+    branch_mapping: &BranchMapping,
+  ) -> miette::Result<String, EcsHelperVarietyError> {
     let branch = std::env::var("CI_COMMIT_BRANCH").or_else(|_| Config::extract_branch_name())?;
 
-    let environment = match branch.as_str() {
-      "master" => "production",
-      "main" => "production",
-      "qa" => "qa",
-      "uat" => "uat",
-      "staging" => "staging",
-      "demo" => "demo",
-      "sandbox" => "sandbox",
-      _ => Err(EcsHelperVarietyError::ExtractEnvironmentError(format!(
+    branch_mapping.environment_for(&branch).ok_or_else(|| {
+      EcsHelperVarietyError::ExtractEnvironmentError(format!(
         "Could not match branch name {branch} with environment."
-      )))?,
-    };
-
-    Ok(environment.to_string())
+      ))
+    })
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::config_file::CONFIG_FILE_NAME;
+  use clap::Parser;
   use git2::{Repository, RepositoryInitOptions};
   use sealed_test::prelude::*;
 
+  #[sealed_test(env = [
+    ("CI_COMMIT_SHA", "1234567890"),
+    ("CI_COMMIT_BRANCH", "master"),
+    ("AWS_REGION", "us-east-1"),
+    ("APPLICATION", "worker"),
+  ])]
+  fn test_new_merges_config_file() {
+    std::fs::write(
+      CONFIG_FILE_NAME,
+      r#"
+        project = "shop"
+        application = "api"
+        aws_account_id = "000000000000"
+        timeout = 900
+
+        [environments.production]
+        cluster = "shop-production"
+        aws_account_id = "123456789012"
+      "#,
+    )
+    .unwrap();
+
+    let args = CommandArguments::parse_from(["ecs_helpers", "--use-image-tag-env-prefix", "login"]);
+    let config = tokio::runtime::Runtime::new()
+      .unwrap()
+      .block_on(Config::new(&args))
+      .unwrap();
+
+    assert_eq!(config.project, "shop");
+    assert_eq!(config.application, "worker");
+    assert_eq!(config.environment, "production");
+    assert_eq!(config.version, "production-1234567890");
+    assert_eq!(config.aws_account_id, "123456789012");
+    assert_eq!(config.cluster.as_deref(), Some("shop-production"));
+    assert_eq!(config.timeout, Some(900));
+
+    let args = CommandArguments::parse_from([
+      "ecs_helpers",
+      "--project",
+      "cli",
+      "--aws-account-id",
+      "999999999999",
+      "login",
+    ]);
+    let config = tokio::runtime::Runtime::new()
+      .unwrap()
+      .block_on(Config::new(&args))
+      .unwrap();
+
+    assert_eq!(config.project, "cli");
+    assert_eq!(config.version, "1234567890");
+    assert_eq!(config.aws_account_id, "999999999999");
+  }
+
+  #[sealed_test(env = [("CI_COMMIT_SHA", "1234567890"), ("CI_COMMIT_BRANCH", "master")])]
+  fn test_new_without_project() {
+    let args = CommandArguments::parse_from(["ecs_helpers", "--application", "api", "login"]);
+    let config = tokio::runtime::Runtime::new()
+      .unwrap()
+      .block_on(Config::new(&args));
+
+    assert!(matches!(
+      config,
+      Err(EcsHelperVarietyError::MissingRequiredOption(option)) if option == "project"
+    ));
+  }
+
+  #[sealed_test(env = [
+    ("CI_COMMIT_SHA", "1234567890"),
+    ("CI_COMMIT_BRANCH", "master"),
+    ("AWS_CONFIG_FILE", "aws_config"),
+    ("AWS_EC2_METADATA_DISABLED", "true"),
+  ])]
+  fn test_new_region() {
+    unsafe {
+      std::env::remove_var("AWS_REGION");
+      std::env::remove_var("AWS_DEFAULT_REGION");
+      std::env::remove_var("AWS_PROFILE");
+    }
+
+    std::fs::write("aws_config", "[profile production]\nregion = eu-west-1\n").unwrap();
+
+    let config_with = |args: &[&str]| {
+      let args = [
+        &[
+          "ecs_helpers",
+          "-p",
+          "shop",
+          "-a",
+          "api",
+          "--aws-account-id",
+          "123456789012",
+        ],
+        args,
+        &["login"],
+      ]
+      .concat();
+
+      tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(Config::new(&CommandArguments::parse_from(args)))
+    };
+
+    assert!(matches!(
+      config_with(&[]),
+      Err(EcsHelperVarietyError::GetRegionError)
+    ));
+    assert_eq!(
+      config_with(&["--region", "ap-south-1"]).unwrap().region,
+      Region::new("ap-south-1")
+    );
+    assert_eq!(
+      config_with(&["--profile", "production"]).unwrap().region,
+      Region::new("eu-west-1")
+    );
+  }
+
+  #[sealed_test(env = [("CI_COMMIT_BRANCH", "release/2.3")])]
+  fn test_extract_environment_with_custom_mapping() {
+    std::fs::write(
+      CONFIG_FILE_NAME,
+      r#"
+        [[branches]]
+        pattern = "release/*"
+        environment = "preprod"
+      "#,
+    )
+    .unwrap();
+
+    let config_file = ConfigFile::load(None).unwrap();
+    let args = CommandArguments::parse_from([
+      "ecs_helpers",
+      "--branch-environment",
+      "release/2.*=staging-2",
+      "login",
+    ]);
+    let branch_mapping = Config::build_branch_mapping(&args, &config_file).unwrap();
+    let environment = Config::extract_environment(&branch_mapping).unwrap();
+    assert_eq!(environment, "staging-2");
+
+    let args = CommandArguments::parse_from(["ecs_helpers", "login"]);
+    let branch_mapping = Config::build_branch_mapping(&args, &config_file).unwrap();
+    let environment = Config::extract_environment(&branch_mapping).unwrap();
+    assert_eq!(environment, "preprod");
+
+    let branch_mapping = BranchMapping::default();
+    let environment = Config::extract_environment(&branch_mapping);
+    assert!(matches!(
+      environment,
+      Err(EcsHelperVarietyError::ExtractEnvironmentError(_))
+    ));
+  }
+
+  #[sealed_test(env = [("CI_COMMIT_BRANCH", "v12")])]
+  fn test_extract_environment_with_regex_quantifier() {
+    let config_file = ConfigFile::load(None).unwrap();
+    let args = CommandArguments::parse_from([
+      "ecs_helpers",
+      "--branch-environment",
+      r"release/*=staging;regex:^v\d{1,2}$=production",
+      "login",
+    ]);
+    assert_eq!(
+      args.branch_environment.clone().unwrap(),
+      ["release/*=staging", r"regex:^v\d{1,2}$=production"]
+    );
+
+    let branch_mapping = Config::build_branch_mapping(&args, &config_file).unwrap();
+    let environment = Config::extract_environment(&branch_mapping).unwrap();
+    assert_eq!(environment, "production");
+  }
+
   #[test]
   fn test_extract_version() {
     let commit_sha = "1234567890".to_string();
     let environment = "production".to_string();
+    let tag_context = TagContext {
+      environment: environment.clone(),
+      commit_sha: commit_sha.clone(),
+      ..TagContext::default()
+    };
 
-    let version = Config::extract_version(false, commit_sha.clone(), &environment);
+    let version = Config::extract_version(false, None, &tag_context).unwrap();
     assert_eq!(version, commit_sha);
 
-    let version = Config::extract_version(true, commit_sha.clone(), &environment);
+    let version = Config::extract_version(true, None, &tag_context).unwrap();
     assert_eq!(version, format!("{}-{}", environment, commit_sha));
+
+    let version =
+      Config::extract_version(true, Some("{environment}-{short_sha}"), &tag_context).unwrap();
+    assert_eq!(version, "production-1234567");
+  }
+
+  #[test]
+  fn test_extract_version_rejects_unstable_placeholders() {
+    let tag_context = TagContext {
+      environment: "production".to_string(),
+      commit_sha: "1234567890".to_string(),
+      branch: Some("main".to_string()),
+      semver: None,
+      date: "20240102".to_string(),
+    };
+
+    for template in ["{date}-{short_sha}", "{branch}-{sha}"] {
+      let error = Config::extract_version(false, Some(template), &tag_context).unwrap_err();
+      assert!(matches!(
+        error,
+        EcsHelperVarietyError::InvalidTagTemplate { .. }
+      ));
+    }
+  }
+
+  #[sealed_test(env = [("CI_COMMIT_TAG", "v2.1.0")])]
+  fn test_extract_semver_from_ci() {
+    assert_eq!(Config::extract_semver(), Some("2.1.0".to_string()));
+  }
+
+  #[sealed_test]
+  fn test_extract_semver_from_git_tag() {
+    unsafe {
+      std::env::remove_var("CI_COMMIT_TAG");
+    }
+
+    let repo = Repository::init(".").unwrap();
+    let signature = git2::Signature::now("ci", "ci@example.com").unwrap();
+    let tree = repo
+      .find_tree(repo.index().unwrap().write_tree().unwrap())
+      .unwrap();
+    let commit = repo
+      .commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+      .unwrap();
+
+    assert_eq!(Config::extract_semver(), None);
+
+    repo
+      .tag_lightweight("v1.4.0", &repo.find_object(commit, None).unwrap(), false)
+      .unwrap();
+
+    assert_eq!(Config::extract_semver(), Some("1.4.0".to_string()));
   }
 
   #[sealed_test]
@@ -171,7 +558,7 @@ mod tests {
       std::env::set_var("CI_COMMIT_BRANCH", "master");
     }
 
-    let environment = Config::extract_environment().unwrap();
+    let environment = Config::extract_environment(&BranchMapping::default()).unwrap();
     assert_eq!(environment, "production");
   }
 
@@ -195,14 +582,14 @@ mod tests {
         .unwrap();
     }
 
-    let environment = Config::extract_environment().unwrap();
+    let environment = Config::extract_environment(&BranchMapping::default()).unwrap();
     assert_eq!(environment, "production");
 
     repo
       .branch("qa", &repo.head().unwrap().peel_to_commit().unwrap(), false)
       .unwrap();
     repo.set_head("refs/heads/qa").unwrap();
-    let environment = Config::extract_environment().unwrap();
+    let environment = Config::extract_environment(&BranchMapping::default()).unwrap();
     assert_eq!(environment, "qa");
 
     repo
@@ -213,7 +600,7 @@ mod tests {
       )
       .unwrap();
     repo.set_head("refs/heads/uat").unwrap();
-    let environment = Config::extract_environment().unwrap();
+    let environment = Config::extract_environment(&BranchMapping::default()).unwrap();
     assert_eq!(environment, "uat");
 
     repo
@@ -224,7 +611,7 @@ mod tests {
       )
       .unwrap();
     repo.set_head("refs/heads/staging").unwrap();
-    let environment = Config::extract_environment().unwrap();
+    let environment = Config::extract_environment(&BranchMapping::default()).unwrap();
     assert_eq!(environment, "staging");
 
     repo
@@ -235,7 +622,7 @@ mod tests {
       )
       .unwrap();
     repo.set_head("refs/heads/demo").unwrap();
-    let environment = Config::extract_environment().unwrap();
+    let environment = Config::extract_environment(&BranchMapping::default()).unwrap();
     assert_eq!(environment, "demo");
 
     repo
@@ -246,7 +633,89 @@ mod tests {
       )
       .unwrap();
     repo.set_head("refs/heads/sandbox").unwrap();
-    let environment = Config::extract_environment().unwrap();
+    let environment = Config::extract_environment(&BranchMapping::default()).unwrap();
     assert_eq!(environment, "sandbox");
   }
+
+  #[tokio::test]
+  async fn test_assume_role() {
+    use aws_config::BehaviorVersion;
+    use aws_sdk_sts::config::Credentials;
+    use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
+    use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+    use aws_smithy_types::body::SdkBody;
+
+    let response = |body: &'static str| {
+      http::Response::builder()
+        .status(200)
+        .body(SdkBody::from(body))
+        .unwrap()
+    };
+    let http_client = StaticReplayClient::new(vec![
+      ReplayEvent::new(
+        HttpRequest::new(SdkBody::from("")),
+        response(
+          r#"<AssumeRoleResponse xmlns="https://sts.amazonaws.com/doc/2011-06-15/">
+            <AssumeRoleResult>
+              <Credentials>
+                <AccessKeyId>ASIAASSUMED</AccessKeyId>
+                <SecretAccessKey>secret</SecretAccessKey>
+                <SessionToken>token</SessionToken>
+                <Expiration>2099-01-01T00:00:00Z</Expiration>
+              </Credentials>
+              <AssumedRoleUser>
+                <Arn>arn:aws:sts::210987654321:assumed-role/deploy/ci</Arn>
+                <AssumedRoleId>AROAEXAMPLE:ci</AssumedRoleId>
+              </AssumedRoleUser>
+            </AssumeRoleResult>
+          </AssumeRoleResponse>"#,
+        ),
+      ),
+      ReplayEvent::new(
+        HttpRequest::new(SdkBody::from("")),
+        response(
+          r#"<GetCallerIdentityResponse xmlns="https://sts.amazonaws.com/doc/2011-06-15/">
+            <GetCallerIdentityResult>
+              <Arn>arn:aws:sts::210987654321:assumed-role/deploy/ci</Arn>
+              <UserId>AROAEXAMPLE:ci</UserId>
+              <Account>210987654321</Account>
+            </GetCallerIdentityResult>
+          </GetCallerIdentityResponse>"#,
+        ),
+      ),
+    ]);
+
+    let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+      .region(Region::new("us-east-1"))
+      .credentials_provider(Credentials::for_tests())
+      .http_client(http_client.clone())
+      .load()
+      .await;
+    let args = AssumeRoleArguments {
+      role_arn: Some("arn:aws:iam::210987654321:role/deploy".to_string()),
+      external_id: Some("shop".to_string()),
+      role_session_name: Some("ci".to_string()),
+      duration: Some(900),
+    };
+
+    let sdk_config = Config::assume_role(sdk_config, &args).await;
+    let aws_account_id = Config::extract_aws_account_id(&sdk_config).await;
+
+    assert_eq!(aws_account_id, "210987654321");
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let assume_role_body = std::str::from_utf8(requests[0].body().bytes().unwrap()).unwrap();
+    assert!(assume_role_body.contains("Action=AssumeRole"));
+    assert!(assume_role_body.contains("RoleArn=arn%3Aaws%3Aiam%3A%3A210987654321%3Arole%2Fdeploy"));
+    assert!(assume_role_body.contains("RoleSessionName=ci"));
+    assert!(assume_role_body.contains("ExternalId=shop"));
+    assert!(assume_role_body.contains("DurationSeconds=900"));
+    assert!(
+      requests[1]
+        .headers()
+        .get("authorization")
+        .unwrap()
+        .contains("ASIAASSUMED")
+    );
+  }
 }
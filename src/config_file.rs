@@ -0,0 +1,235 @@
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::errors::EcsHelperVarietyError;
+
+pub const CONFIG_FILE_NAME: &str = ".ecs_helpers.toml";
+
+/// Settings which could be set globally or overridden per environment.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EnvironmentSettings {
+  pub aws_account_id: Option<String>,
+  pub cluster: Option<String>,
+  pub service: Option<String>,
+  pub timeout: Option<u64>,
+  pub build_args: Option<Vec<String>>,
+}
+
+impl EnvironmentSettings {
+  /// Fills empty values of `self` from `fallback`.
+  fn or(self, fallback: EnvironmentSettings) -> Self {
+    Self {
+      aws_account_id: self.aws_account_id.or(fallback.aws_account_id),
+      cluster: self.cluster.or(fallback.cluster),
+      service: self.service.or(fallback.service),
+      timeout: self.timeout.or(fallback.timeout),
+      build_args: self.build_args.or(fallback.build_args),
+    }
+  }
+}
+
+/// Branch to environment rule, see [`crate::branch_mapping::BranchRule`] for the pattern syntax.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BranchRuleConfig {
+  pub pattern: String,
+  pub environment: String,
+}
+
+/// Project-level configuration stored in `.ecs_helpers.toml`.
+///
+/// ```toml
+/// project = "shop"
+/// application = "api"
+/// timeout = 900
+///
+/// [environments.production]
+/// cluster = "shop-production"
+/// aws_account_id = "123456789012"
+///
+/// [[branches]]
+/// pattern = "release/*"
+/// environment = "staging"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+  pub project: Option<String>,
+  pub application: Option<String>,
+  pub use_image_tag_env_prefix: Option<bool>,
+  /// Template of the image version tag, e.g. `{environment}-{short_sha}`.
+  pub version_template: Option<String>,
+
+  #[serde(flatten)]
+  pub settings: EnvironmentSettings,
+
+  pub environments: HashMap<String, EnvironmentSettings>,
+
+  pub branches: Vec<BranchRuleConfig>,
+}
+
+impl ConfigFile {
+  /// Loads config from the given path or, if there is no path, from the first
+  /// `.ecs_helpers.toml` found walking up from the current directory.
+  pub fn load(path: Option<&Path>) -> miette::Result<Self, EcsHelperVarietyError> {
+    let path = match path {
+      Some(path) => path.to_path_buf(),
+      None => {
+        let current_dir = std::env::current_dir()?;
+
+        match ConfigFile::discover(&current_dir) {
+          Some(path) => path,
+          None => return Ok(ConfigFile::default()),
+        }
+      }
+    };
+
+    log::debug!("Load config file: {}", path.display());
+
+    let content = std::fs::read_to_string(&path).map_err(|err| {
+      EcsHelperVarietyError::ReadConfigFileError(format!("{}: {err}", path.display()))
+    })?;
+
+    ConfigFile::parse(&content)
+  }
+
+  pub fn parse(content: &str) -> miette::Result<Self, EcsHelperVarietyError> {
+    toml::from_str(content).map_err(EcsHelperVarietyError::ParseConfigFileError)
+  }
+
+  fn discover(from: &Path) -> Option<PathBuf> {
+    from
+      .ancestors()
+      .map(|directory| directory.join(CONFIG_FILE_NAME))
+      .find(|path| path.is_file())
+  }
+
+  /// Returns settings of the environment section merged with the top-level ones.
+  pub fn settings_for(&self, environment: &str) -> EnvironmentSettings {
+    let environment_settings = self
+      .environments
+      .get(environment)
+      .cloned()
+      .unwrap_or_default();
+
+    environment_settings.or(self.settings.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sealed_test::prelude::*;
+
+  const CONFIG: &str = r#"
+    project = "shop"
+    application = "api"
+    use_image_tag_env_prefix = true
+    timeout = 900
+    build_args = ["NODE_ENV=production"]
+
+    [environments.production]
+    cluster = "shop-production"
+    aws_account_id = "123456789012"
+
+    [environments.staging]
+    timeout = 300
+
+    [[branches]]
+    pattern = "release/*"
+    environment = "staging"
+
+    [[branches]]
+    pattern = "regex:review/(.*)"
+    environment = "review-$1"
+  "#;
+
+  #[test]
+  fn test_parse() {
+    let config_file = ConfigFile::parse(CONFIG).unwrap();
+
+    assert_eq!(config_file.project.as_deref(), Some("shop"));
+    assert_eq!(config_file.application.as_deref(), Some("api"));
+    assert_eq!(config_file.use_image_tag_env_prefix, Some(true));
+    assert_eq!(config_file.settings.timeout, Some(900));
+    assert_eq!(config_file.environments.len(), 2);
+    assert_eq!(
+      config_file.branches,
+      vec![
+        BranchRuleConfig {
+          pattern: "release/*".to_string(),
+          environment: "staging".to_string(),
+        },
+        BranchRuleConfig {
+          pattern: "regex:review/(.*)".to_string(),
+          environment: "review-$1".to_string(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_invalid() {
+    let config_file = ConfigFile::parse("timeout = \"soon\"");
+
+    assert!(matches!(
+      config_file,
+      Err(EcsHelperVarietyError::ParseConfigFileError(_))
+    ));
+  }
+
+  #[test]
+  fn test_settings_for() {
+    let config_file = ConfigFile::parse(CONFIG).unwrap();
+
+    let production = config_file.settings_for("production");
+    assert_eq!(production.cluster.as_deref(), Some("shop-production"));
+    assert_eq!(production.aws_account_id.as_deref(), Some("123456789012"));
+    assert_eq!(production.timeout, Some(900));
+    assert_eq!(
+      production.build_args,
+      Some(vec!["NODE_ENV=production".to_string()])
+    );
+
+    let staging = config_file.settings_for("staging");
+    assert_eq!(staging.cluster, None);
+    assert_eq!(staging.timeout, Some(300));
+
+    let unknown = config_file.settings_for("qa");
+    assert_eq!(unknown, config_file.settings);
+  }
+
+  #[sealed_test]
+  fn test_load_discovers_file_in_parent_directory() {
+    std::fs::write(CONFIG_FILE_NAME, CONFIG).unwrap();
+    std::fs::create_dir_all("apps/api").unwrap();
+    std::env::set_current_dir("apps/api").unwrap();
+
+    let config_file = ConfigFile::load(None).unwrap();
+
+    assert_eq!(config_file.project.as_deref(), Some("shop"));
+  }
+
+  #[sealed_test]
+  fn test_load_from_explicit_path() {
+    std::fs::write("custom.toml", "project = \"custom\"").unwrap();
+
+    let config_file = ConfigFile::load(Some(Path::new("custom.toml"))).unwrap();
+
+    assert_eq!(config_file.project.as_deref(), Some("custom"));
+  }
+
+  #[sealed_test]
+  fn test_load_missing_explicit_path() {
+    let config_file = ConfigFile::load(Some(Path::new("missing.toml")));
+
+    assert!(matches!(
+      config_file,
+      Err(EcsHelperVarietyError::ReadConfigFileError(_))
+    ));
+  }
+}
@@ -0,0 +1,151 @@
+use std::ffi::OsStr;
+
+use tokio::process::Command;
+
+use crate::{args::ContainerEngine, errors::EcsHelperVarietyError};
+
+/// Engines in the order they are looked for on `PATH`.
+const ENGINES: [ContainerEngine; 4] = [
+  ContainerEngine::Docker,
+  ContainerEngine::Podman,
+  ContainerEngine::Nerdctl,
+  ContainerEngine::Buildah,
+];
+
+pub fn program(engine: ContainerEngine) -> &'static str {
+  match engine {
+    ContainerEngine::Docker => "docker",
+    ContainerEngine::Podman => "podman",
+    ContainerEngine::Nerdctl => "nerdctl",
+    ContainerEngine::Buildah => "buildah",
+  }
+}
+
+/// Creates command of the engine CLI, subcommands and arguments are added by the caller.
+pub fn command(engine: ContainerEngine) -> Command {
+  Command::new(program(engine))
+}
+
+/// Finds the first engine which executable is in one of the `PATH` directories.
+pub fn detect(path: &OsStr) -> Option<ContainerEngine> {
+  ENGINES.into_iter().find(|engine| {
+    std::env::split_paths(path).any(|directory| directory.join(program(*engine)).is_file())
+  })
+}
+
+/// Returns the selected engine, the detected one or docker when nothing is found.
+pub fn resolve(engine: Option<ContainerEngine>) -> ContainerEngine {
+  engine
+    .or_else(|| std::env::var_os("PATH").and_then(|path| detect(&path)))
+    .unwrap_or(ContainerEngine::Docker)
+}
+
+fn to_args(values: &[&str]) -> Vec<String> {
+  values.iter().map(|value| value.to_string()).collect()
+}
+
+/// Returns arguments which join the images into the manifest list.
+pub fn manifest_create_args(
+  engine: ContainerEngine,
+  manifest_list: &str,
+  images: &[String],
+) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
+  if engine == ContainerEngine::Nerdctl {
+    return Err(unsupported(engine, "manifest lists"));
+  }
+
+  let mut args = to_args(&["manifest", "create", "--amend", manifest_list]);
+  args.extend(images.iter().cloned());
+
+  Ok(args)
+}
+
+/// Returns arguments which push the manifest list with all its images.
+pub fn manifest_push_args(
+  engine: ContainerEngine,
+  manifest_list: &str,
+) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
+  match engine {
+    ContainerEngine::Docker => Ok(to_args(&["manifest", "push", "--purge", manifest_list])),
+    ContainerEngine::Podman | ContainerEngine::Buildah => Ok(to_args(&[
+      "manifest",
+      "push",
+      "--all",
+      manifest_list,
+      &format!("docker://{manifest_list}"),
+    ])),
+    ContainerEngine::Nerdctl => Err(unsupported(engine, "manifest lists")),
+  }
+}
+
+pub fn unsupported(engine: ContainerEngine, operation: &str) -> EcsHelperVarietyError {
+  EcsHelperVarietyError::UnsupportedContainerEngine {
+    engine: program(engine).to_string(),
+    operation: operation.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sealed_test::prelude::*;
+
+  const LIST: &str = "123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.0";
+
+  #[sealed_test]
+  fn test_detect() {
+    for (directory, program) in [("podman_bin", "podman"), ("buildah_bin", "buildah")] {
+      std::fs::create_dir(directory).unwrap();
+      std::fs::write(format!("{directory}/{program}"), "").unwrap();
+    }
+
+    assert_eq!(
+      detect(OsStr::new("empty:buildah_bin:podman_bin")),
+      Some(ContainerEngine::Podman)
+    );
+    assert_eq!(
+      detect(OsStr::new("buildah_bin")),
+      Some(ContainerEngine::Buildah)
+    );
+    assert_eq!(detect(OsStr::new("empty")), None);
+  }
+
+  #[test]
+  fn test_manifest_create_args() {
+    let images = vec![format!("{LIST}-linux-amd64"), format!("{LIST}-linux-arm64")];
+
+    assert_eq!(
+      manifest_create_args(ContainerEngine::Podman, LIST, &images).unwrap(),
+      vec![
+        "manifest".to_string(),
+        "create".to_string(),
+        "--amend".to_string(),
+        LIST.to_string(),
+        images[0].to_owned(),
+        images[1].to_owned(),
+      ]
+    );
+    assert!(matches!(
+      manifest_create_args(ContainerEngine::Nerdctl, LIST, &images),
+      Err(EcsHelperVarietyError::UnsupportedContainerEngine { engine, .. }) if engine == "nerdctl"
+    ));
+  }
+
+  #[test]
+  fn test_manifest_push_args() {
+    assert_eq!(
+      manifest_push_args(ContainerEngine::Docker, LIST).unwrap(),
+      vec!["manifest", "push", "--purge", LIST]
+    );
+    assert_eq!(
+      manifest_push_args(ContainerEngine::Buildah, LIST).unwrap(),
+      vec![
+        "manifest".to_string(),
+        "push".to_string(),
+        "--all".to_string(),
+        LIST.to_string(),
+        format!("docker://{LIST}"),
+      ]
+    );
+  }
+}
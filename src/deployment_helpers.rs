@@ -0,0 +1,433 @@
+use std::collections::HashSet;
+
+use aws_sdk_ecs::types::{Deployment, DeploymentRolloutState, Service, ServiceEvent};
+use aws_smithy_types::date_time::Format;
+
+use crate::errors::EcsHelperVarietyError;
+
+const PRIMARY_DEPLOYMENT_STATUS: &str = "PRIMARY";
+
+/// Deployment started by `update_service` which we're waiting for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedDeployment {
+  pub id: String,
+  pub task_definition_arn: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeploymentProgress {
+  InProgress,
+  Completed,
+}
+
+pub fn primary_deployment(service: &Service) -> Option<&Deployment> {
+  service
+    .deployments()
+    .iter()
+    .find(|deployment| deployment.status() == Some(PRIMARY_DEPLOYMENT_STATUS))
+}
+
+pub fn track_primary_deployment(
+  service: &Service,
+) -> miette::Result<TrackedDeployment, EcsHelperVarietyError> {
+  let deployment = primary_deployment(service).ok_or(EcsHelperVarietyError::NoPrimaryDeployment)?;
+
+  Ok(TrackedDeployment {
+    id: deployment.id().unwrap_or_default().to_string(),
+    task_definition_arn: deployment.task_definition().unwrap_or_default().to_string(),
+  })
+}
+
+/// Checks state of the tracked deployment.
+///
+/// The deployment is failed when its `rolloutState` is `FAILED` or when it is no longer the
+/// primary one, e.g. the deployment circuit breaker has started a rollback.
+/// For services without `rolloutState` the deployment is completed once it is the only one
+/// and all desired tasks are running.
+pub fn deployment_progress(
+  service: &Service,
+  tracked_deployment: &TrackedDeployment,
+) -> miette::Result<DeploymentProgress, EcsHelperVarietyError> {
+  let primary = primary_deployment(service).ok_or(EcsHelperVarietyError::NoPrimaryDeployment)?;
+
+  if primary.id() != Some(tracked_deployment.id.as_str()) {
+    return Err(EcsHelperVarietyError::DeploymentFailed {
+      deployment_id: tracked_deployment.id.to_owned(),
+      reason: format!(
+        "deployment was replaced by {} with task definition {}",
+        primary.id().unwrap_or("unknown"),
+        primary.task_definition().unwrap_or("unknown")
+      ),
+    });
+  }
+
+  match primary.rollout_state() {
+    Some(DeploymentRolloutState::Completed) => Ok(DeploymentProgress::Completed),
+    Some(DeploymentRolloutState::Failed) => Err(EcsHelperVarietyError::DeploymentFailed {
+      deployment_id: tracked_deployment.id.to_owned(),
+      reason: primary
+        .rollout_state_reason()
+        .unwrap_or("rollout failed")
+        .to_string(),
+    }),
+    Some(_) => Ok(DeploymentProgress::InProgress),
+    None => {
+      let is_single_deployment = service.deployments().len() == 1;
+      let is_scaled = primary.running_count() == primary.desired_count();
+
+      if is_single_deployment && is_scaled {
+        Ok(DeploymentProgress::Completed)
+      } else {
+        Ok(DeploymentProgress::InProgress)
+      }
+    }
+  }
+}
+
+/// Returns service events which are not in `seen_event_ids` yet, oldest first, and marks them as seen.
+///
+/// ECS returns events newest first.
+pub fn new_service_events<'a>(
+  service: &'a Service,
+  seen_event_ids: &mut HashSet<String>,
+) -> Vec<&'a ServiceEvent> {
+  let mut events = service
+    .events()
+    .iter()
+    .filter(|event| {
+      let id = event.id().unwrap_or_default();
+      seen_event_ids.insert(id.to_string())
+    })
+    .collect::<Vec<_>>();
+
+  events.reverse();
+
+  events
+}
+
+pub fn format_service_event(event: &ServiceEvent) -> String {
+  let created_at = event
+    .created_at()
+    .and_then(|created_at| created_at.fmt(Format::DateTime).ok())
+    .unwrap_or_default();
+
+  format!("{created_at} {}", event.message().unwrap_or_default())
+}
+
+pub fn format_deployment_counts(deployment: &Deployment) -> String {
+  format!(
+    "Deployment {}: running {}, pending {}, desired {}",
+    deployment.id().unwrap_or("unknown"),
+    deployment.running_count(),
+    deployment.pending_count(),
+    deployment.desired_count()
+  )
+}
+
+/// Formats the summary of several deployed services, results are task definition ARNs by service name.
+///
+/// Returns the summary lines and fails with the names of the failed services.
+pub fn summarize_deployments(
+  results: Vec<(&str, miette::Result<String, EcsHelperVarietyError>)>,
+  dry_run: bool,
+) -> (Vec<String>, miette::Result<(), EcsHelperVarietyError>) {
+  let mut summary = vec!["Deployment summary:".to_string()];
+  let mut failed_services = vec![];
+
+  for (service_name, result) in results {
+    match result {
+      Ok(_) if dry_run => summary.push(format!("  {service_name}: planned")),
+      Ok(task_definition_arn) => {
+        summary.push(format!("  {service_name}: deployed {task_definition_arn}"))
+      }
+      Err(error) => {
+        summary.push(format!("  {service_name}: failed, {error}"));
+        failed_services.push(service_name);
+      }
+    }
+  }
+
+  if !failed_services.is_empty() {
+    return (
+      summary,
+      Err(EcsHelperVarietyError::DeploymentsFailed(
+        failed_services.join(", "),
+      )),
+    );
+  }
+
+  (summary, Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tracked_deployment() -> TrackedDeployment {
+    TrackedDeployment {
+      id: "ecs-svc/2".to_string(),
+      task_definition_arn: "arn:aws:ecs:us-east-1:123456789012:task-definition/web:2".to_string(),
+    }
+  }
+
+  fn deployment(
+    id: &str,
+    status: &str,
+    rollout_state: Option<DeploymentRolloutState>,
+  ) -> Deployment {
+    Deployment::builder()
+      .id(id)
+      .status(status)
+      .set_rollout_state(rollout_state)
+      .rollout_state_reason("ECS deployment circuit breaker: tasks failed to start.")
+      .task_definition(format!(
+        "arn:aws:ecs:us-east-1:123456789012:task-definition/web:{}",
+        id.trim_start_matches("ecs-svc/")
+      ))
+      .desired_count(2)
+      .running_count(2)
+      .build()
+  }
+
+  fn event(id: &str, created_at: i64, message: &str) -> ServiceEvent {
+    ServiceEvent::builder()
+      .id(id)
+      .created_at(aws_smithy_types::DateTime::from_secs(created_at))
+      .message(message)
+      .build()
+  }
+
+  #[test]
+  fn test_new_service_events() {
+    let mut seen_event_ids = HashSet::new();
+
+    let service = Service::builder()
+      .events(event(
+        "2",
+        1_700_000_010,
+        "(service web) has started 1 tasks",
+      ))
+      .events(event(
+        "1",
+        1_700_000_000,
+        "(service web) registered 1 targets",
+      ))
+      .build();
+
+    let events = new_service_events(&service, &mut seen_event_ids)
+      .into_iter()
+      .map(format_service_event)
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      events,
+      vec![
+        "2023-11-14T22:13:20Z (service web) registered 1 targets",
+        "2023-11-14T22:13:30Z (service web) has started 1 tasks",
+      ]
+    );
+
+    let service = Service::builder()
+      .events(event(
+        "3",
+        1_700_000_020,
+        "(service web) has reached a steady state.",
+      ))
+      .events(event(
+        "2",
+        1_700_000_010,
+        "(service web) has started 1 tasks",
+      ))
+      .events(event(
+        "1",
+        1_700_000_000,
+        "(service web) registered 1 targets",
+      ))
+      .build();
+
+    let events = new_service_events(&service, &mut seen_event_ids);
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id(), Some("3"));
+  }
+
+  #[test]
+  fn test_format_deployment_counts() {
+    let deployment = Deployment::builder()
+      .id("ecs-svc/2")
+      .running_count(1)
+      .pending_count(1)
+      .desired_count(2)
+      .build();
+
+    assert_eq!(
+      format_deployment_counts(&deployment),
+      "Deployment ecs-svc/2: running 1, pending 1, desired 2"
+    );
+  }
+
+  #[test]
+  fn test_track_primary_deployment() {
+    let service = Service::builder()
+      .deployments(deployment("ecs-svc/1", "ACTIVE", None))
+      .deployments(deployment(
+        "ecs-svc/2",
+        "PRIMARY",
+        Some(DeploymentRolloutState::InProgress),
+      ))
+      .build();
+
+    assert_eq!(
+      track_primary_deployment(&service).unwrap(),
+      tracked_deployment()
+    );
+  }
+
+  #[test]
+  fn test_deployment_in_progress() {
+    let service = Service::builder()
+      .deployments(deployment(
+        "ecs-svc/2",
+        "PRIMARY",
+        Some(DeploymentRolloutState::InProgress),
+      ))
+      .deployments(deployment("ecs-svc/1", "ACTIVE", None))
+      .build();
+
+    assert_eq!(
+      deployment_progress(&service, &tracked_deployment()).unwrap(),
+      DeploymentProgress::InProgress
+    );
+  }
+
+  #[test]
+  fn test_deployment_completed() {
+    let service = Service::builder()
+      .deployments(deployment(
+        "ecs-svc/2",
+        "PRIMARY",
+        Some(DeploymentRolloutState::Completed),
+      ))
+      .build();
+
+    assert_eq!(
+      deployment_progress(&service, &tracked_deployment()).unwrap(),
+      DeploymentProgress::Completed
+    );
+  }
+
+  #[test]
+  fn test_deployment_failed() {
+    let service = Service::builder()
+      .deployments(deployment(
+        "ecs-svc/2",
+        "PRIMARY",
+        Some(DeploymentRolloutState::Failed),
+      ))
+      .build();
+
+    let progress = deployment_progress(&service, &tracked_deployment());
+
+    assert!(matches!(
+      progress,
+      Err(EcsHelperVarietyError::DeploymentFailed { reason, .. })
+        if reason.contains("circuit breaker")
+    ));
+  }
+
+  #[test]
+  fn test_deployment_replaced_by_circuit_breaker_rollback() {
+    let service = Service::builder()
+      .deployments(deployment(
+        "ecs-svc/3",
+        "PRIMARY",
+        Some(DeploymentRolloutState::Completed),
+      ))
+      .build();
+
+    let progress = deployment_progress(&service, &tracked_deployment());
+
+    assert!(matches!(
+      progress,
+      Err(EcsHelperVarietyError::DeploymentFailed { reason, .. })
+        if reason.contains("replaced by ecs-svc/3")
+    ));
+  }
+
+  #[test]
+  fn test_deployment_without_rollout_state() {
+    let service = Service::builder()
+      .deployments(deployment("ecs-svc/2", "PRIMARY", None))
+      .deployments(deployment("ecs-svc/1", "ACTIVE", None))
+      .build();
+
+    assert_eq!(
+      deployment_progress(&service, &tracked_deployment()).unwrap(),
+      DeploymentProgress::InProgress
+    );
+
+    let service = Service::builder()
+      .deployments(deployment("ecs-svc/2", "PRIMARY", None))
+      .build();
+
+    assert_eq!(
+      deployment_progress(&service, &tracked_deployment()).unwrap(),
+      DeploymentProgress::Completed
+    );
+  }
+
+  #[test]
+  fn test_summarize_deployments() {
+    let (summary, result) = summarize_deployments(
+      vec![
+        (
+          "web",
+          Ok("arn:aws:ecs:us-east-1:123456789012:task-definition/web:2".to_string()),
+        ),
+        (
+          "worker",
+          Err(EcsHelperVarietyError::WaitTaskTimeoutError(600)),
+        ),
+        ("cron", Err(EcsHelperVarietyError::NoPrimaryDeployment)),
+      ],
+      false,
+    );
+
+    assert_eq!(
+      summary,
+      [
+        "Deployment summary:".to_string(),
+        "  web: deployed arn:aws:ecs:us-east-1:123456789012:task-definition/web:2".to_string(),
+        format!(
+          "  worker: failed, {}",
+          EcsHelperVarietyError::WaitTaskTimeoutError(600)
+        ),
+        format!(
+          "  cron: failed, {}",
+          EcsHelperVarietyError::NoPrimaryDeployment
+        ),
+      ]
+    );
+    assert!(matches!(
+      result,
+      Err(EcsHelperVarietyError::DeploymentsFailed(services)) if services == "worker, cron"
+    ));
+  }
+
+  #[test]
+  fn test_summarize_planned_deployments() {
+    let (summary, result) = summarize_deployments(
+      vec![
+        ("web", Ok("web:1".to_string())),
+        ("worker", Ok("worker:1".to_string())),
+      ],
+      true,
+    );
+
+    assert_eq!(
+      summary,
+      ["Deployment summary:", "  web: planned", "  worker: planned"]
+    );
+    assert!(result.is_ok());
+  }
+}
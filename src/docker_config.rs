@@ -0,0 +1,289 @@
+use std::path::{Path, PathBuf};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde_json::{Map, Value, json};
+
+use crate::errors::EcsHelperVarietyError;
+
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// Returns path of the docker config file, the same way as the docker CLI does:
+/// `$DOCKER_CONFIG/config.json` or `~/.docker/config.json`.
+pub fn config_path(config_file: Option<&Path>) -> miette::Result<PathBuf, EcsHelperVarietyError> {
+  if let Some(config_file) = config_file {
+    return Ok(config_file.to_path_buf());
+  }
+
+  if let Some(docker_config) = std::env::var_os("DOCKER_CONFIG") {
+    return Ok(PathBuf::from(docker_config).join(CONFIG_FILE_NAME));
+  }
+
+  let home = std::env::var_os("HOME")
+    .or_else(|| std::env::var_os("USERPROFILE"))
+    .ok_or_else(|| {
+      EcsHelperVarietyError::DockerConfigError("home directory is not set".to_string())
+    })?;
+
+  Ok(PathBuf::from(home).join(".docker").join(CONFIG_FILE_NAME))
+}
+
+/// Sets `auths.<registry>.auth` entry, other settings of the config are kept as is.
+pub fn add_auth(
+  mut config: Value,
+  registry: &str,
+  username: &str,
+  password: &str,
+) -> miette::Result<Value, EcsHelperVarietyError> {
+  let config_object = config.as_object_mut().ok_or_else(|| {
+    EcsHelperVarietyError::DockerConfigError("config is not a JSON object".to_string())
+  })?;
+
+  let auths = config_object
+    .entry("auths")
+    .or_insert_with(|| Value::Object(Map::new()))
+    .as_object_mut()
+    .ok_or_else(|| {
+      EcsHelperVarietyError::DockerConfigError("auths is not a JSON object".to_string())
+    })?;
+
+  auths.insert(
+    registry.to_string(),
+    json!({ "auth": STANDARD.encode(format!("{username}:{password}")) }),
+  );
+
+  Ok(config)
+}
+
+/// Returns credential helper configured for the registry, docker prefers it over `auths`.
+pub fn credential_helper_for<'a>(config: &'a Value, registry: &str) -> Option<&'a str> {
+  config
+    .get("credHelpers")
+    .and_then(|cred_helpers| cred_helpers.get(registry))
+    .or_else(|| config.get("credsStore"))
+    .and_then(Value::as_str)
+}
+
+/// Adds registry credentials to the docker config file, the file is created when it doesn't exist.
+pub fn store_auth(
+  config_path: &Path,
+  registry: &str,
+  username: &str,
+  password: &str,
+) -> miette::Result<(), EcsHelperVarietyError> {
+  let config = match std::fs::read_to_string(config_path) {
+    Ok(content) if !content.trim().is_empty() => serde_json::from_str(&content).map_err(|err| {
+      EcsHelperVarietyError::DockerConfigError(format!("{}: {err}", config_path.display()))
+    })?,
+    Ok(_) => json!({}),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => json!({}),
+    Err(err) => {
+      return Err(EcsHelperVarietyError::DockerConfigError(format!(
+        "{}: {err}",
+        config_path.display()
+      )));
+    }
+  };
+
+  if let Some(credential_helper) = credential_helper_for(&config, registry) {
+    log::warn!(
+      "Docker will use docker-credential-{credential_helper} for {registry} instead of the stored credentials"
+    );
+  }
+
+  let config = add_auth(config, registry, username, password)?;
+  let content = serde_json::to_string_pretty(&config)
+    .map_err(|err| EcsHelperVarietyError::DockerConfigError(err.to_string()))?;
+
+  write_config(config_path, &content).map_err(|err| {
+    EcsHelperVarietyError::DockerConfigError(format!("{}: {err}", config_path.display()))
+  })
+}
+
+/// Replaces the config file atomically, so a failed write can't truncate it.
+///
+/// The file holds registry tokens, a new file is readable only by the owner,
+/// mode of an existing file is kept.
+fn write_config(config_path: &Path, content: &str) -> std::io::Result<()> {
+  let directory = match config_path.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent,
+    _ => Path::new("."),
+  };
+  std::fs::create_dir_all(directory)?;
+
+  let file_name = config_path
+    .file_name()
+    .map(|file_name| file_name.to_string_lossy().into_owned())
+    .unwrap_or_else(|| CONFIG_FILE_NAME.to_string());
+  let temp_path = directory.join(format!(".{file_name}.{}.tmp", std::process::id()));
+
+  let result = write_temp_file(config_path, &temp_path, content)
+    .and_then(|_| std::fs::rename(&temp_path, config_path));
+
+  if result.is_err() {
+    let _ = std::fs::remove_file(&temp_path);
+  }
+
+  result
+}
+
+#[cfg(unix)]
+fn write_temp_file(config_path: &Path, temp_path: &Path, content: &str) -> std::io::Result<()> {
+  use std::io::Write;
+  use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+  let mode = match std::fs::metadata(config_path) {
+    Ok(metadata) => metadata.permissions().mode() & 0o7777,
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0o600,
+    Err(err) => return Err(err),
+  };
+
+  let mut file = std::fs::OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .mode(mode)
+    .open(temp_path)?;
+  // the mode passed to open is reduced by umask
+  file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+  file.write_all(content.as_bytes())?;
+  file.sync_all()
+}
+
+#[cfg(not(unix))]
+fn write_temp_file(_config_path: &Path, temp_path: &Path, content: &str) -> std::io::Result<()> {
+  std::fs::write(temp_path, content)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sealed_test::prelude::*;
+
+  const REGISTRY: &str = "123456789012.dkr.ecr.us-east-1.amazonaws.com";
+
+  #[test]
+  fn test_add_auth() {
+    let config = json!({
+      "auths": { "ghcr.io": { "auth": "Z2hjcjp0b2tlbg==" } },
+      "credHelpers": { "gcr.io": "gcloud" },
+    });
+
+    let config = add_auth(config, REGISTRY, "AWS", "token").unwrap();
+
+    assert_eq!(
+      config,
+      json!({
+        "auths": {
+          "ghcr.io": { "auth": "Z2hjcjp0b2tlbg==" },
+          REGISTRY: { "auth": "QVdTOnRva2Vu" },
+        },
+        "credHelpers": { "gcr.io": "gcloud" },
+      })
+    );
+  }
+
+  #[test]
+  fn test_add_auth_to_invalid_config() {
+    assert!(matches!(
+      add_auth(json!({ "auths": [] }), REGISTRY, "AWS", "token"),
+      Err(EcsHelperVarietyError::DockerConfigError(_))
+    ));
+  }
+
+  #[test]
+  fn test_credential_helper_for() {
+    let config = json!({ "credHelpers": { REGISTRY: "ecr-login" } });
+    assert_eq!(credential_helper_for(&config, REGISTRY), Some("ecr-login"));
+
+    let config = json!({ "credsStore": "desktop" });
+    assert_eq!(credential_helper_for(&config, REGISTRY), Some("desktop"));
+
+    assert_eq!(credential_helper_for(&json!({}), REGISTRY), None);
+  }
+
+  #[sealed_test(env = [("DOCKER_CONFIG", "docker")])]
+  fn test_config_path_from_docker_config() {
+    assert_eq!(config_path(None).unwrap(), Path::new("docker/config.json"));
+    assert_eq!(
+      config_path(Some(Path::new("custom.json"))).unwrap(),
+      Path::new("custom.json")
+    );
+  }
+
+  #[sealed_test(env = [("HOME", "/home/ci")])]
+  fn test_config_path_from_home() {
+    unsafe {
+      std::env::remove_var("DOCKER_CONFIG");
+    }
+
+    assert_eq!(
+      config_path(None).unwrap(),
+      Path::new("/home/ci/.docker/config.json")
+    );
+  }
+
+  #[cfg(unix)]
+  fn mode(path: &Path) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+  }
+
+  #[cfg(unix)]
+  #[sealed_test]
+  fn test_store_auth_merges_existing_config() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let config_path = Path::new("config.json");
+    std::fs::write(
+      config_path,
+      r#"{ "auths": { "ghcr.io": { "auth": "Z2hjcjp0b2tlbg==" } }, "credsStore": "desktop" }"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(config_path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+    store_auth(config_path, REGISTRY, "AWS", "token").unwrap();
+
+    let config: Value =
+      serde_json::from_str(&std::fs::read_to_string(config_path).unwrap()).unwrap();
+    assert_eq!(
+      config,
+      json!({
+        "auths": {
+          "ghcr.io": { "auth": "Z2hjcjp0b2tlbg==" },
+          REGISTRY: { "auth": "QVdTOnRva2Vu" },
+        },
+        "credsStore": "desktop",
+      })
+    );
+    assert_eq!(mode(config_path), 0o640);
+    assert_eq!(
+      std::fs::read_dir(".").unwrap().count(),
+      1,
+      "temporary file is left"
+    );
+  }
+
+  #[cfg(unix)]
+  #[sealed_test]
+  fn test_store_auth_creates_private_file() {
+    let config_path = Path::new("docker/config.json");
+
+    store_auth(config_path, REGISTRY, "AWS", "token").unwrap();
+
+    assert_eq!(mode(config_path), 0o600);
+  }
+
+  #[sealed_test]
+  fn test_store_auth() {
+    let config_path = Path::new("docker/config.json");
+
+    store_auth(config_path, REGISTRY, "AWS", "token").unwrap();
+    store_auth(config_path, "ghcr.io", "ghcr", "token").unwrap();
+
+    let config: Value =
+      serde_json::from_str(&std::fs::read_to_string(config_path).unwrap()).unwrap();
+
+    assert_eq!(config["auths"][REGISTRY]["auth"], "QVdTOnRva2Vu");
+    assert_eq!(config["auths"]["ghcr.io"]["auth"], "Z2hjcjp0b2tlbg==");
+  }
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeSet;
+
+use crate::ecr::{self, EcrRegistry};
+
+/// Returns image references of the `FROM` instructions, e.g. `FROM --platform=linux/amd64 ruby:3.3 AS base`.
+pub fn base_images(content: &str) -> Vec<&str> {
+  content
+    .lines()
+    .filter_map(|line| {
+      let mut words = line.split_whitespace();
+      let instruction = words.next()?;
+
+      if !instruction.eq_ignore_ascii_case("FROM") {
+        return None;
+      }
+
+      words.find(|word| !word.starts_with("--"))
+    })
+    .collect()
+}
+
+/// Returns private ECR registries of the base images.
+///
+/// Images built from `ARG` values are skipped, they can't be resolved before the build.
+pub fn ecr_registries(content: &str) -> Vec<EcrRegistry> {
+  base_images(content)
+    .into_iter()
+    .filter(|image| !image.contains('$'))
+    .filter_map(ecr::parse_registry)
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DOCKERFILE: &str = "
+    ARG BASE_IMAGE=ruby:3.3
+    FROM --platform=linux/amd64 210987654321.dkr.ecr.eu-west-1.amazonaws.com/base/ruby:3.3 AS build
+    RUN bundle install
+
+    from 123456789012.dkr.ecr.us-east-1.amazonaws.com/base/nginx:1.27
+    FROM 210987654321.dkr.ecr.eu-west-1.amazonaws.com/base/node@sha256:8a3b
+    FROM ${BASE_IMAGE}
+    FROM build
+    COPY --from=210987654321.dkr.ecr.us-east-1.amazonaws.com/base/tools:1.0 /bin/tool /bin/tool
+  ";
+
+  #[test]
+  fn test_base_images() {
+    assert_eq!(
+      base_images(DOCKERFILE),
+      vec![
+        "210987654321.dkr.ecr.eu-west-1.amazonaws.com/base/ruby:3.3",
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com/base/nginx:1.27",
+        "210987654321.dkr.ecr.eu-west-1.amazonaws.com/base/node@sha256:8a3b",
+        "${BASE_IMAGE}",
+        "build",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_ecr_registries() {
+    assert_eq!(
+      ecr_registries(DOCKERFILE),
+      vec![
+        EcrRegistry {
+          account_id: "123456789012".to_string(),
+          region: Some("us-east-1".to_string()),
+        },
+        EcrRegistry {
+          account_id: "210987654321".to_string(),
+          region: Some("eu-west-1".to_string()),
+        },
+      ]
+    );
+  }
+}
@@ -1,12 +1,72 @@
-use aws_config::SdkConfig;
+use aws_config::{Region, SdkConfig};
 use aws_sdk_ecr::{
   Client,
-  types::{ImageDetail, ImageIdentifier, Repository},
+  types::{AuthorizationData, Image, ImageDetail, ImageIdentifier, Repository},
 };
 use aws_sdk_ecs::types::ContainerDefinition;
 use base64::{Engine, alphabet, engine};
 
-use crate::errors::EcsHelperVarietyError;
+use crate::{
+  errors::EcsHelperVarietyError,
+  image_overrides::{ImageOverride, ImageOverrides},
+};
+
+const OCI_IMAGE_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const DOCKER_MANIFEST_LIST: &str = "application/vnd.docker.distribution.manifest.list.v2+json";
+const OCI_IMAGE_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const DOCKER_IMAGE_MANIFEST: &str = "application/vnd.docker.distribution.manifest.v2+json";
+
+/// Credentials of the ECR registry, username is always `AWS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryAuthorization {
+  /// Registry host without scheme, e.g. `123456789012.dkr.ecr.us-east-1.amazonaws.com`.
+  pub registry: String,
+  pub username: String,
+  pub password: String,
+}
+
+impl RegistryAuthorization {
+  /// Decodes base64 `username:password` token of the registry.
+  pub(crate) fn from_token(
+    registry: &str,
+    token: &str,
+  ) -> miette::Result<Self, EcsHelperVarietyError> {
+    let base_64_engine =
+      engine::GeneralPurpose::new(&alphabet::STANDARD, engine::general_purpose::PAD);
+
+    let token = base_64_engine
+      .decode(token)
+      .map_err(EcsHelperVarietyError::ParseTokenError)?;
+    let token = String::from_utf8(token).map_err(EcsHelperVarietyError::ParseTokenFromUtf8Error)?;
+    let (username, password) = token
+      .split_once(':')
+      .ok_or(EcsHelperVarietyError::ExtractTokenError)?;
+
+    Ok(Self {
+      registry: registry.to_string(),
+      username: username.to_string(),
+      password: password.to_string(),
+    })
+  }
+
+  fn from_authorization_data(
+    auth_data: &AuthorizationData,
+  ) -> miette::Result<Self, EcsHelperVarietyError> {
+    let token = auth_data
+      .authorization_token()
+      .ok_or(EcsHelperVarietyError::ExtractTokenError)?;
+
+    let registry = auth_data
+      .proxy_endpoint()
+      .ok_or(EcsHelperVarietyError::ExtractTokenError)?;
+    let registry = registry
+      .strip_prefix("https://")
+      .unwrap_or(registry)
+      .trim_end_matches('/');
+
+    Self::from_token(registry, token)
+  }
+}
 
 pub struct EcrClient {
   client: Client,
@@ -19,17 +79,35 @@ impl EcrClient {
     }
   }
 
+  /// Creates client for the region of the registry if it differs from the SDK config one.
+  pub fn for_region(config: &SdkConfig, region: Option<&String>) -> Self {
+    match region {
+      Some(region) => {
+        let config = config
+          .to_builder()
+          .region(Region::new(region.to_owned()))
+          .build();
+
+        EcrClient::new(&config)
+      }
+      None => EcrClient::new(config),
+    }
+  }
+
   pub async fn get_private_repositories(
     &self,
   ) -> miette::Result<Vec<Repository>, EcsHelperVarietyError> {
-    let response = self
+    let repositories = self
       .client
       .describe_repositories()
+      .into_paginator()
+      .items()
       .send()
+      .try_collect()
       .await
-      .map_err(EcsHelperVarietyError::DescribeRepositoriesError)?;
+      .map_err(|error| EcsHelperVarietyError::DescribeRepositoriesError(Box::new(error)))?;
 
-    Ok(response.repositories().to_vec())
+    Ok(repositories)
   }
 
   pub async fn describe_images(
@@ -41,10 +119,15 @@ impl EcrClient {
       .client
       .describe_images()
       .repository_name(repository_name)
-      .image_ids(image_id)
+      .image_ids(image_id.clone())
       .send()
       .await
-      .map_err(EcsHelperVarietyError::DescribeImagesError)?;
+      .map_err(|err| match err.as_service_error() {
+        Some(service_error) if service_error.is_image_not_found_exception() => {
+          image_not_found(repository_name, &image_id)
+        }
+        _ => EcsHelperVarietyError::DescribeImagesError(Box::new(err)),
+      })?;
 
     let image_details = response
       .image_details()
@@ -54,91 +137,328 @@ impl EcrClient {
     Ok(image_details.to_owned())
   }
 
+  /// Checks if the image exists in the repository.
+  pub async fn image_exists(
+    &self,
+    repository_name: &str,
+    image_id: ImageIdentifier,
+  ) -> miette::Result<bool, EcsHelperVarietyError> {
+    match self.describe_images(repository_name, image_id).await {
+      Ok(_) => Ok(true),
+      Err(EcsHelperVarietyError::ImageNotFound { .. }) => Ok(false),
+      Err(err) => Err(err),
+    }
+  }
+
+  /// Starts a manual scan of the image, a scan which is already running is not an error.
+  pub async fn start_image_scan(
+    &self,
+    repository_name: &str,
+    image_id: ImageIdentifier,
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let result = self
+      .client
+      .start_image_scan()
+      .repository_name(repository_name)
+      .image_id(image_id)
+      .send()
+      .await;
+
+    match result {
+      Ok(_) => Ok(()),
+      Err(err)
+        if err
+          .as_service_error()
+          .is_some_and(|service_error| service_error.is_limit_exceeded_exception()) =>
+      {
+        Ok(())
+      }
+      Err(err) => Err(EcsHelperVarietyError::StartImageScanError(Box::new(err))),
+    }
+  }
+
+  /// Returns the image with its manifest, a manifest list is returned as is.
+  pub async fn get_image(
+    &self,
+    repository_name: &str,
+    image_id: ImageIdentifier,
+  ) -> miette::Result<Image, EcsHelperVarietyError> {
+    let response = self
+      .client
+      .batch_get_image()
+      .repository_name(repository_name)
+      .image_ids(image_id.clone())
+      .accepted_media_types(OCI_IMAGE_INDEX)
+      .accepted_media_types(DOCKER_MANIFEST_LIST)
+      .accepted_media_types(OCI_IMAGE_MANIFEST)
+      .accepted_media_types(DOCKER_IMAGE_MANIFEST)
+      .send()
+      .await
+      .map_err(|error| EcsHelperVarietyError::BatchGetImageError(Box::new(error)))?;
+
+    response
+      .images()
+      .first()
+      .cloned()
+      .ok_or_else(|| image_not_found(repository_name, &image_id))
+  }
+
+  /// Returns the image manifest, e.g. to check platform images of a manifest list.
+  pub async fn get_image_manifest(
+    &self,
+    repository_name: &str,
+    image_id: ImageIdentifier,
+  ) -> miette::Result<String, EcsHelperVarietyError> {
+    let image = self.get_image(repository_name, image_id).await?;
+
+    image
+      .image_manifest()
+      .map(str::to_string)
+      .ok_or(EcsHelperVarietyError::ExtractImageError)
+  }
+
+  /// Adds the tag to the image, nothing is changed when the tag already points to it.
+  pub async fn tag_image(
+    &self,
+    repository_name: &str,
+    image: &Image,
+    tag: &str,
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let result = self
+      .client
+      .put_image()
+      .repository_name(repository_name)
+      .set_image_manifest(image.image_manifest().map(str::to_string))
+      .set_image_manifest_media_type(image.image_manifest_media_type().map(str::to_string))
+      .image_tag(tag)
+      .send()
+      .await;
+
+    match result {
+      Ok(_) => Ok(()),
+      Err(err)
+        if err
+          .as_service_error()
+          .is_some_and(|service_error| service_error.is_image_already_exists_exception()) =>
+      {
+        Ok(())
+      }
+      Err(err) => Err(EcsHelperVarietyError::PutImageError(Box::new(err))),
+    }
+  }
+
   /// Extracts token from AWS ECR.
   ///
   /// Doing the same as `aws ecr get-login-password --region=` but without aws cli.
   pub async fn get_token(&self) -> miette::Result<String, EcsHelperVarietyError> {
+    Ok(self.get_authorization().await?.password)
+  }
+
+  /// Extracts registry and its credentials from AWS ECR.
+  pub async fn get_authorization(
+    &self,
+  ) -> miette::Result<RegistryAuthorization, EcsHelperVarietyError> {
+    self
+      .get_authorizations(&[])
+      .await?
+      .into_iter()
+      .next()
+      .ok_or(EcsHelperVarietyError::ExtractTokenError)
+  }
+
+  /// Extracts credentials of the registries with the given ids, the default registry of the
+  /// account is used when ids are empty.
+  pub async fn get_authorizations(
+    &self,
+    registry_ids: &[String],
+  ) -> miette::Result<Vec<RegistryAuthorization>, EcsHelperVarietyError> {
+    let registry_ids = (!registry_ids.is_empty()).then(|| registry_ids.to_vec());
+
+    #[allow(deprecated)]
     let auth_token_data = self
       .client
       .get_authorization_token()
+      .set_registry_ids(registry_ids)
       .send()
       .await
-      .map_err(EcsHelperVarietyError::GetTokenError)?;
-    let auth_data = auth_token_data
-      .authorization_data()
-      .first()
-      .ok_or(EcsHelperVarietyError::ExtractTokenError)?;
-
-    let base_64_engine =
-      engine::GeneralPurpose::new(&alphabet::STANDARD, engine::general_purpose::PAD);
+      .map_err(|error| EcsHelperVarietyError::GetTokenError(Box::new(error)))?;
 
-    let token = auth_data
-      .authorization_token()
-      .ok_or(EcsHelperVarietyError::ExtractTokenError)?;
-    let token = base_64_engine
-      .decode(token)
-      .map_err(EcsHelperVarietyError::ParseTokenError)?;
-    let token = token.as_slice();
-    let token =
-      String::from_utf8(token.to_vec()).map_err(EcsHelperVarietyError::ParseTokenFromUtf8Error)?;
-    let token = token.split(':').collect::<Vec<&str>>();
-    let token = token
-      .get(1)
-      .ok_or(EcsHelperVarietyError::ExtractTokenError)?;
-
-    Ok(token.to_string())
+    auth_token_data
+      .authorization_data()
+      .iter()
+      .map(RegistryAuthorization::from_authorization_data)
+      .collect()
   }
 
   /// Creates new container definition from existing container definition.
   ///
-  /// Set image to `repository_uri:version`, `repository_uri` is extracted from existing container definition.
+  /// Set image to `repository_uri:version` when the image is stored in the private `registry`.
+  /// Image override of the container takes precedence over the version, overridden images from
+  /// other registries are used as is.
+  /// Returns `None` for not overridden images from other registries, such containers should be kept
+  /// unchanged.
+  ///
+  /// With `pin_digest` images stored in ECR are set to `repository_uri@sha256:...`, so the task
+  /// definition is not affected by tags pushed later.
   pub async fn create_new_container_definition_from(
     &self,
     container_definition: &ContainerDefinition,
-    repositories: Vec<Repository>,
+    registry: &EcrRegistry,
     version: &String,
-  ) -> miette::Result<ContainerDefinition, EcsHelperVarietyError> {
-    let repository = repositories
-      .iter()
-      .find(|repository| {
-        let repository_uri = match repository.repository_uri() {
-          Some(repository_uri) => repository_uri,
-          None => return false,
-        };
+    image_overrides: &ImageOverrides,
+    pin_digest: bool,
+  ) -> miette::Result<Option<ContainerDefinition>, EcsHelperVarietyError> {
+    let Some(image) = container_definition.image() else {
+      return Ok(None);
+    };
 
-        let image = match container_definition.image() {
-          Some(image) => image,
-          None => return false,
-        };
+    let image_override = container_definition
+      .name()
+      .and_then(|name| image_overrides.get(name));
 
-        image.contains(repository_uri)
-      })
-      .unwrap();
+    let mut new_image = match image_override {
+      Some(ImageOverride::Image(new_image)) => new_image.to_owned(),
+      Some(ImageOverride::Tag(tag)) => format!("{}:{tag}", image_repository(image)),
+      None => format!("{}:{version}", image_repository(image)),
+    };
 
-    let repository_name = repository.repository_name().unwrap();
-    let repository_uri = repository.repository_uri().unwrap();
+    match private_repository_name(&new_image, registry) {
+      Some(repository_name) => {
+        let image_detail = self
+          .describe_images(repository_name, image_identifier(&new_image))
+          .await?;
 
-    let ecr_base = repository_uri.split('/').collect::<Vec<_>>();
-    let ecr_base = ecr_base.first().unwrap();
+        if pin_digest {
+          let image_digest = image_detail
+            .image_digest()
+            .ok_or(EcsHelperVarietyError::ExtractImageError)?;
 
-    if !container_definition.image().unwrap().contains(ecr_base) {
-      return Err(EcsHelperVarietyError::ContainerDefinitionImageError(
-        container_definition.image().unwrap().to_owned(),
-      ));
+          new_image = format!("{}@{image_digest}", image_repository(&new_image));
+        }
+      }
+      None if image_override.is_none() => return Ok(None),
+      None => {}
+    }
+
+    let mut new_container_definition = container_definition.clone();
+    new_container_definition.image = Some(new_image);
+
+    Ok(Some(new_container_definition))
+  }
+}
+
+fn image_not_found(repository_name: &str, image_id: &ImageIdentifier) -> EcsHelperVarietyError {
+  EcsHelperVarietyError::ImageNotFound {
+    repository: repository_name.to_string(),
+    image: image_id
+      .image_tag()
+      .or(image_id.image_digest())
+      .unwrap_or_default()
+      .to_string(),
+  }
+}
+
+/// Private ECR registry of the account, in the region of the SDK config when the region is not set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EcrRegistry {
+  pub account_id: String,
+  pub region: Option<String>,
+}
+
+impl EcrRegistry {
+  /// Parses registry passed as `account_id`, `account_id:region` or the registry host.
+  pub fn parse(registry: &str) -> miette::Result<Self, EcsHelperVarietyError> {
+    if let Some(registry) = parse_registry(registry) {
+      return Ok(registry);
+    }
+
+    let (account_id, region) = match registry.split_once(':') {
+      Some((account_id, region)) => (account_id, Some(region.to_string())),
+      None => (registry, None),
     };
 
-    let image_identifier = ImageIdentifier::builder().image_tag(version).build();
+    if !is_account_id(account_id) || region.as_ref().is_some_and(|region| region.is_empty()) {
+      return Err(EcsHelperVarietyError::InvalidRegistry(registry.to_string()));
+    }
 
-    self
-      .describe_images(repository_name, image_identifier)
-      .await?;
+    Ok(Self {
+      account_id: account_id.to_string(),
+      region,
+    })
+  }
+}
 
-    let mut new_container_definition = container_definition.clone();
+fn is_account_id(account_id: &str) -> bool {
+  account_id.len() == 12 && account_id.chars().all(|char| char.is_ascii_digit())
+}
+
+/// Extracts account id and region from the private ECR registry host or image,
+/// e.g. `123456789012.dkr.ecr.us-east-1.amazonaws.com`.
+pub fn parse_registry(registry: &str) -> Option<EcrRegistry> {
+  let host = registry.trim_start_matches("https://").split('/').next()?;
+  let (account_id, rest) = host.split_once(".dkr.ecr.")?;
+  let (region, domain) = rest.split_once('.')?;
+
+  let is_aws_domain = domain.starts_with("amazonaws.com");
 
-    // we're partially cloning container definition because we need to change image according to repository
-    new_container_definition.image = Some(format!("{repository_uri}:{version}"));
+  (is_aws_domain && is_account_id(account_id)).then(|| EcrRegistry {
+    account_id: account_id.to_string(),
+    region: Some(region.to_string()),
+  })
+}
 
-    Ok(new_container_definition)
+/// Returns the repository name of the image stored in the private `registry`, e.g. `shop` of
+/// `123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.0`.
+pub fn private_repository_name<'a>(image: &'a str, registry: &EcrRegistry) -> Option<&'a str> {
+  if parse_registry(image).as_ref() != Some(registry) {
+    return None;
+  }
+
+  image_repository(image)
+    .split_once('/')
+    .map(|(_, repository_name)| repository_name)
+}
+
+/// Strips tag and digest from the image reference.
+fn image_repository(image: &str) -> &str {
+  let image = image
+    .split_once('@')
+    .map_or(image, |(repository, _)| repository);
+
+  match image.rsplit_once(':') {
+    // colon could also separate registry host and port
+    Some((repository, tag)) if !tag.contains('/') => repository,
+    _ => image,
+  }
+}
+
+/// Digest or tag of the image reference, docker uses `latest` when both are omitted.
+pub fn image_identifier(image: &str) -> ImageIdentifier {
+  if let Some((_, digest)) = image.split_once('@') {
+    return ImageIdentifier::builder().image_digest(digest).build();
+  }
+
+  let tag = match image.rsplit_once(':') {
+    Some((_, tag)) if !tag.contains('/') => tag,
+    _ => "latest",
+  };
+
+  ImageIdentifier::builder().image_tag(tag).build()
+}
+
+/// Describes how the container image was changed by `create_new_container_definition_from`.
+pub fn format_image_update(
+  container_definition: &ContainerDefinition,
+  new_container_definition: Option<&ContainerDefinition>,
+) -> String {
+  let name = container_definition.name().unwrap_or_default();
+  let image = container_definition.image().unwrap_or_default();
+
+  match new_container_definition
+    .and_then(|new_container_definition| new_container_definition.image())
+  {
+    Some(new_image) => format!("Container {name}: {image} -> {new_image}"),
+    None => format!("Container {name}: {image} kept, image is not stored in ECR"),
   }
 }
 
@@ -208,4 +528,614 @@ mod tests {
 
     assert_eq!(image_detail.image_digest(), Some("sha256:1234567890"));
   }
+
+  fn sdk_config_with(http_client: StaticReplayClient) -> SdkConfig {
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client)
+      .build()
+  }
+
+  fn account_registry() -> EcrRegistry {
+    registry("123456789012", Some("us-east-1"))
+  }
+
+  #[tokio::test]
+  async fn test_get_private_repositories() {
+    let first_page = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{\"repositories\": [{\"repositoryName\": \"shop\"}], \"nextToken\": \"page-2\"}",
+      ))
+      .unwrap();
+    let second_page = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{\"repositories\": [{\"repositoryName\": \"shop-web\"}]}",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), first_page),
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), second_page),
+    ]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client.clone()));
+    let repositories = client.get_private_repositories().await.unwrap();
+
+    assert_eq!(
+      repositories
+        .iter()
+        .map(|repository| repository.repository_name().unwrap())
+        .collect::<Vec<_>>(),
+      ["shop", "shop-web"]
+    );
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let request: serde_json::Value =
+      serde_json::from_slice(requests[1].body().bytes().unwrap()).unwrap();
+    assert_eq!(request["nextToken"], "page-2");
+  }
+
+  #[test]
+  fn test_private_repository_name() {
+    let registry = account_registry();
+
+    assert_eq!(
+      private_repository_name(
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com/team/shop:1.0",
+        &registry
+      ),
+      Some("team/shop")
+    );
+    assert_eq!(
+      private_repository_name(
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com/shop@sha256:1234567890",
+        &registry
+      ),
+      Some("shop")
+    );
+    assert_eq!(
+      private_repository_name(
+        "210987654321.dkr.ecr.us-east-1.amazonaws.com/shop:1.0",
+        &registry
+      ),
+      None
+    );
+    assert_eq!(
+      private_repository_name(
+        "123456789012.dkr.ecr.eu-west-1.amazonaws.com/shop:1.0",
+        &registry
+      ),
+      None
+    );
+    assert_eq!(private_repository_name("nginx:latest", &registry), None);
+  }
+
+  #[test]
+  fn test_image_repository() {
+    assert_eq!(
+      image_repository("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.0"),
+      "123456789012.dkr.ecr.us-east-1.amazonaws.com/shop"
+    );
+    assert_eq!(
+      image_repository("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop@sha256:1234567890"),
+      "123456789012.dkr.ecr.us-east-1.amazonaws.com/shop"
+    );
+    assert_eq!(
+      image_repository("registry.local:5000/shop"),
+      "registry.local:5000/shop"
+    );
+    assert_eq!(image_repository("nginx"), "nginx");
+  }
+
+  #[tokio::test]
+  async fn test_create_new_container_definition_from() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{ \"imageDetails\": [{ \"repositoryName\": \"shop\", \"imageTags\": [\"1.1\"] }] }",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![ReplayEvent::new(request, response)]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client.clone()));
+    let container_definition = ContainerDefinition::builder()
+      .name("web")
+      .image("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.0")
+      .build();
+
+    let new_container_definition = client
+      .create_new_container_definition_from(
+        &container_definition,
+        &account_registry(),
+        &"1.1".to_string(),
+        &ImageOverrides::default(),
+        false,
+      )
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(
+      new_container_definition.image(),
+      Some("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.1")
+    );
+    assert_eq!(
+      format_image_update(&container_definition, Some(&new_container_definition)),
+      "Container web: 123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.0 -> 123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.1"
+    );
+
+    // image should be looked up in the exactly matched repository
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let body = std::str::from_utf8(requests[0].body().bytes().unwrap()).unwrap();
+    assert!(body.contains("\"repositoryName\":\"shop\""));
+  }
+
+  #[tokio::test]
+  async fn test_create_new_container_definition_from_other_registry() {
+    let http_client = StaticReplayClient::new(vec![]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client.clone()));
+    let container_definition = ContainerDefinition::builder()
+      .name("nginx")
+      .image("nginx:latest")
+      .build();
+
+    let new_container_definition = client
+      .create_new_container_definition_from(
+        &container_definition,
+        &account_registry(),
+        &"1.1".to_string(),
+        &ImageOverrides::default(),
+        false,
+      )
+      .await
+      .unwrap();
+
+    assert!(new_container_definition.is_none());
+    assert_eq!(http_client.actual_requests().count(), 0);
+    assert_eq!(
+      format_image_update(&container_definition, None),
+      "Container nginx: nginx:latest kept, image is not stored in ECR"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_create_new_container_definition_from_missing_tag() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(400)
+      .body(SdkBody::from(
+        "{ \"__type\": \"ImageNotFoundException\", \"message\": \"The image with imageId {imageTag:'1.1'} does not exist\" }",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![ReplayEvent::new(request, response)]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client));
+    let container_definition = ContainerDefinition::builder()
+      .name("web")
+      .image("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop-web:1.0")
+      .build();
+
+    let result = client
+      .create_new_container_definition_from(
+        &container_definition,
+        &account_registry(),
+        &"1.1".to_string(),
+        &ImageOverrides::default(),
+        false,
+      )
+      .await;
+
+    assert!(matches!(
+      result,
+      Err(EcsHelperVarietyError::ImageNotFound { repository, image })
+        if repository == "shop-web" && image == "1.1"
+    ));
+  }
+
+  #[test]
+  fn test_image_identifier() {
+    let identifier =
+      image_identifier("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop@sha256:1234567890");
+    assert_eq!(identifier.image_digest(), Some("sha256:1234567890"));
+    assert_eq!(identifier.image_tag(), None);
+
+    let identifier = image_identifier("registry.local:5000/shop:1.0");
+    assert_eq!(identifier.image_tag(), Some("1.0"));
+
+    let identifier = image_identifier("registry.local:5000/shop");
+    assert_eq!(identifier.image_tag(), Some("latest"));
+  }
+
+  #[tokio::test]
+  async fn test_create_new_container_definition_from_with_overrides() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{ \"imageDetails\": [{ \"repositoryName\": \"shop\", \"imageTags\": [\"3f2a1bc\"] }] }",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![ReplayEvent::new(request, response)]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client.clone()));
+    let image_overrides = ImageOverrides::parse(&[
+      "web=3f2a1bc".to_string(),
+      "nginx=nginx:1.27".to_string(),
+      "envoy=v1.31".to_string(),
+    ])
+    .unwrap();
+    let version = "1.1".to_string();
+
+    let container_definition = ContainerDefinition::builder()
+      .name("web")
+      .image("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.0")
+      .build();
+    let new_container_definition = client
+      .create_new_container_definition_from(
+        &container_definition,
+        &account_registry(),
+        &version,
+        &image_overrides,
+        false,
+      )
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(
+      new_container_definition.image(),
+      Some("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:3f2a1bc")
+    );
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let body = std::str::from_utf8(requests[0].body().bytes().unwrap()).unwrap();
+    assert!(body.contains("\"imageTag\":\"3f2a1bc\""));
+
+    // images from other registries are not checked
+    let container_definition = ContainerDefinition::builder()
+      .name("nginx")
+      .image("nginx:1.25")
+      .build();
+    let new_container_definition = client
+      .create_new_container_definition_from(
+        &container_definition,
+        &account_registry(),
+        &version,
+        &image_overrides,
+        false,
+      )
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(new_container_definition.image(), Some("nginx:1.27"));
+
+    let container_definition = ContainerDefinition::builder()
+      .name("envoy")
+      .image("public.ecr.aws/appmesh/aws-appmesh-envoy:v1.29")
+      .build();
+    let new_container_definition = client
+      .create_new_container_definition_from(
+        &container_definition,
+        &account_registry(),
+        &version,
+        &image_overrides,
+        false,
+      )
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(
+      new_container_definition.image(),
+      Some("public.ecr.aws/appmesh/aws-appmesh-envoy:v1.31")
+    );
+    assert_eq!(http_client.actual_requests().count(), 1);
+  }
+
+  #[tokio::test]
+  async fn test_create_new_container_definition_from_with_pinned_digest() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{ \"imageDetails\": [{ \"repositoryName\": \"shop\", \"imageTags\": [\"1.1\"], \"imageDigest\": \"sha256:1234567890\" }] }",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![ReplayEvent::new(request, response)]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client));
+    let container_definition = ContainerDefinition::builder()
+      .name("web")
+      .image("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop@sha256:0987654321")
+      .build();
+
+    let new_container_definition = client
+      .create_new_container_definition_from(
+        &container_definition,
+        &account_registry(),
+        &"1.1".to_string(),
+        &ImageOverrides::default(),
+        true,
+      )
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(
+      new_container_definition.image(),
+      Some("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop@sha256:1234567890")
+    );
+  }
+
+  fn registry(account_id: &str, region: Option<&str>) -> EcrRegistry {
+    EcrRegistry {
+      account_id: account_id.to_string(),
+      region: region.map(str::to_string),
+    }
+  }
+
+  #[test]
+  fn test_parse_registry() {
+    assert_eq!(
+      parse_registry("123456789012.dkr.ecr.eu-west-1.amazonaws.com"),
+      Some(registry("123456789012", Some("eu-west-1")))
+    );
+    assert_eq!(
+      parse_registry("https://123456789012.dkr.ecr.cn-north-1.amazonaws.com.cn/shop:1.0"),
+      Some(registry("123456789012", Some("cn-north-1")))
+    );
+    assert_eq!(parse_registry("ghcr.io"), None);
+    assert_eq!(parse_registry("public.ecr.aws"), None);
+    assert_eq!(parse_registry("shop.dkr.ecr.us-east-1.example.com"), None);
+  }
+
+  #[tokio::test]
+  async fn test_get_authorization() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "
+        {
+          \"authorizationData\": [
+            {
+              \"authorizationToken\": \"QVdTOnRva2Vu\",
+              \"proxyEndpoint\": \"https://123456789012.dkr.ecr.us-east-1.amazonaws.com\"
+            }
+          ]
+        }
+        ",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![ReplayEvent::new(request, response)]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client));
+    let authorization = client.get_authorization().await.unwrap();
+
+    assert_eq!(
+      authorization,
+      RegistryAuthorization {
+        registry: "123456789012.dkr.ecr.us-east-1.amazonaws.com".to_string(),
+        username: "AWS".to_string(),
+        password: "token".to_string(),
+      }
+    );
+  }
+
+  #[test]
+  fn test_parse_ecr_registry() {
+    assert_eq!(
+      EcrRegistry::parse("123456789012").unwrap(),
+      registry("123456789012", None)
+    );
+    assert_eq!(
+      EcrRegistry::parse("123456789012:eu-west-1").unwrap(),
+      registry("123456789012", Some("eu-west-1"))
+    );
+    assert_eq!(
+      EcrRegistry::parse("123456789012.dkr.ecr.eu-west-1.amazonaws.com").unwrap(),
+      registry("123456789012", Some("eu-west-1"))
+    );
+
+    for invalid_registry in ["tooling", "12345:eu-west-1", "123456789012:", "ghcr.io"] {
+      assert!(matches!(
+        EcrRegistry::parse(invalid_registry),
+        Err(EcsHelperVarietyError::InvalidRegistry(_))
+      ));
+    }
+  }
+
+  #[tokio::test]
+  async fn test_get_authorizations() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "
+        {
+          \"authorizationData\": [
+            {
+              \"authorizationToken\": \"QVdTOnRva2Vu\",
+              \"proxyEndpoint\": \"https://123456789012.dkr.ecr.us-east-1.amazonaws.com\"
+            },
+            {
+              \"authorizationToken\": \"QVdTOnRva2Vu\",
+              \"proxyEndpoint\": \"https://210987654321.dkr.ecr.us-east-1.amazonaws.com\"
+            }
+          ]
+        }
+        ",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![ReplayEvent::new(request, response)]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client.clone()));
+    let authorizations = client
+      .get_authorizations(&["123456789012".to_string(), "210987654321".to_string()])
+      .await
+      .unwrap();
+
+    assert_eq!(
+      authorizations
+        .iter()
+        .map(|authorization| authorization.registry.as_str())
+        .collect::<Vec<_>>(),
+      vec![
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com",
+        "210987654321.dkr.ecr.us-east-1.amazonaws.com"
+      ]
+    );
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let body = std::str::from_utf8(requests[0].body().bytes().unwrap()).unwrap();
+    assert!(body.contains("\"registryIds\":[\"123456789012\",\"210987654321\"]"));
+  }
+
+  #[tokio::test]
+  async fn test_get_image_manifest() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "
+        {
+          \"images\": [
+            {
+              \"imageId\": { \"imageTag\": \"1.0\" },
+              \"imageManifest\": \"{\\\"schemaVersion\\\": 2}\",
+              \"imageManifestMediaType\": \"application/vnd.oci.image.index.v1+json\",
+              \"registryId\": \"123456789012\",
+              \"repositoryName\": \"shop\"
+            }
+          ],
+          \"failures\": []
+        }
+        ",
+      ))
+      .unwrap();
+    let not_found_response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "
+        {
+          \"images\": [],
+          \"failures\": [
+            {
+              \"imageId\": { \"imageTag\": \"2.0\" },
+              \"failureCode\": \"ImageNotFound\",
+              \"failureReason\": \"Requested image not found\"
+            }
+          ]
+        }
+        ",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), response),
+      ReplayEvent::new(request, not_found_response),
+    ]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client));
+
+    let manifest = client
+      .get_image_manifest("shop", ImageIdentifier::builder().image_tag("1.0").build())
+      .await
+      .unwrap();
+    assert_eq!(manifest, "{\"schemaVersion\": 2}");
+
+    let not_found = client
+      .get_image_manifest("shop", ImageIdentifier::builder().image_tag("2.0").build())
+      .await;
+    assert!(matches!(
+      not_found,
+      Err(EcsHelperVarietyError::ImageNotFound { repository, image })
+        if repository == "shop" && image == "2.0"
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_image_exists() {
+    let found_response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{ \"imageDetails\": [{ \"imageDigest\": \"sha256:1234567890\", \"imageTags\": [\"1.0\"] }] }",
+      ))
+      .unwrap();
+    let not_found_response = http::Response::builder()
+      .status(400)
+      .body(SdkBody::from(
+        "{ \"__type\": \"ImageNotFoundException\", \"message\": \"The image with imageId {imageTag:'1.1'} does not exist\" }",
+      ))
+      .unwrap();
+    let repository_not_found_response = http::Response::builder()
+      .status(400)
+      .body(SdkBody::from(
+        "{ \"__type\": \"RepositoryNotFoundException\", \"message\": \"The repository does not exist\" }",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(
+      [
+        found_response,
+        not_found_response,
+        repository_not_found_response,
+      ]
+      .into_iter()
+      .map(|response| ReplayEvent::new(HttpRequest::new(SdkBody::from("")), response))
+      .collect(),
+    );
+
+    let client = EcrClient::new(&sdk_config_with(http_client));
+    let image_id = |tag: &str| ImageIdentifier::builder().image_tag(tag).build();
+
+    assert!(client.image_exists("shop", image_id("1.0")).await.unwrap());
+    assert!(!client.image_exists("shop", image_id("1.1")).await.unwrap());
+    assert!(matches!(
+      client.image_exists("missing", image_id("1.0")).await,
+      Err(EcsHelperVarietyError::DescribeImagesError(_))
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_tag_image() {
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{ \"image\": { \"imageId\": { \"imageTag\": \"latest\" }, \"repositoryName\": \"shop\" } }",
+      ))
+      .unwrap();
+    let already_exists_response = http::Response::builder()
+      .status(400)
+      .body(SdkBody::from(
+        "{ \"__type\": \"ImageAlreadyExistsException\", \"message\": \"Image with digest and tag 'latest' already exists\" }",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), response),
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), already_exists_response),
+    ]);
+
+    let client = EcrClient::new(&sdk_config_with(http_client.clone()));
+    let image = Image::builder()
+      .image_manifest("{\"schemaVersion\": 2}")
+      .image_manifest_media_type(OCI_IMAGE_MANIFEST)
+      .build();
+
+    client.tag_image("shop", &image, "latest").await.unwrap();
+    client.tag_image("shop", &image, "latest").await.unwrap();
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let body = std::str::from_utf8(requests[0].body().bytes().unwrap()).unwrap();
+    assert!(body.contains("\"imageTag\":\"latest\""));
+    assert!(
+      body.contains("\"imageManifestMediaType\":\"application/vnd.oci.image.manifest.v1+json\"")
+    );
+  }
 }
@@ -0,0 +1,215 @@
+use aws_config::{Region, SdkConfig};
+use aws_sdk_ecrpublic::{
+  Client,
+  types::{ImageIdentifier, Repository},
+};
+
+use crate::{ecr::RegistryAuthorization, errors::EcsHelperVarietyError};
+
+/// Registry host of all ECR Public repositories.
+pub const PUBLIC_REGISTRY: &str = "public.ecr.aws";
+
+/// ECR Public API is available only in us-east-1.
+const PUBLIC_REGION: &str = "us-east-1";
+
+pub struct EcrPublicClient {
+  client: Client,
+}
+
+impl EcrPublicClient {
+  pub fn new(config: &SdkConfig) -> Self {
+    let config = config
+      .to_builder()
+      .region(Region::new(PUBLIC_REGION))
+      .build();
+
+    Self {
+      client: Client::new(&config),
+    }
+  }
+
+  /// Extracts credentials of the public registry, they are valid for all public repositories.
+  pub async fn get_authorization(
+    &self,
+  ) -> miette::Result<RegistryAuthorization, EcsHelperVarietyError> {
+    let response = self
+      .client
+      .get_authorization_token()
+      .send()
+      .await
+      .map_err(|error| EcsHelperVarietyError::GetPublicTokenError(Box::new(error)))?;
+
+    let token = response
+      .authorization_data()
+      .and_then(|authorization_data| authorization_data.authorization_token())
+      .ok_or(EcsHelperVarietyError::ExtractTokenError)?;
+
+    RegistryAuthorization::from_token(PUBLIC_REGISTRY, token)
+  }
+
+  pub async fn get_repositories(&self) -> miette::Result<Vec<Repository>, EcsHelperVarietyError> {
+    let repositories = self
+      .client
+      .describe_repositories()
+      .into_paginator()
+      .items()
+      .send()
+      .try_collect()
+      .await
+      .map_err(|error| EcsHelperVarietyError::DescribePublicRepositoriesError(Box::new(error)))?;
+
+    Ok(repositories)
+  }
+
+  /// Checks if the image tag exists in the public repository.
+  pub async fn image_exists(
+    &self,
+    repository_name: &str,
+    image_tag: &str,
+  ) -> miette::Result<bool, EcsHelperVarietyError> {
+    let result = self
+      .client
+      .describe_images()
+      .repository_name(repository_name)
+      .image_ids(ImageIdentifier::builder().image_tag(image_tag).build())
+      .send()
+      .await;
+
+    match result {
+      Ok(response) => Ok(!response.image_details().is_empty()),
+      Err(err)
+        if err
+          .as_service_error()
+          .is_some_and(|service_error| service_error.is_image_not_found_exception()) =>
+      {
+        Ok(false)
+      }
+      Err(err) => Err(EcsHelperVarietyError::DescribePublicImagesError(Box::new(
+        err,
+      ))),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aws_config::BehaviorVersion;
+  use aws_sdk_ecrpublic::config::{Credentials, SharedCredentialsProvider};
+  use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
+  use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+  use aws_smithy_types::body::SdkBody;
+
+  fn sdk_config_with(http_client: StaticReplayClient) -> SdkConfig {
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    SdkConfig::builder()
+      .region(Region::new("eu-west-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client)
+      .build()
+  }
+
+  #[tokio::test]
+  async fn test_get_authorization() {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "
+        {
+          \"authorizationData\": {
+            \"authorizationToken\": \"QVdTOnRva2Vu\",
+            \"expiresAt\": 1760000000
+          }
+        }
+        ",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![ReplayEvent::new(request, response)]);
+
+    let client = EcrPublicClient::new(&sdk_config_with(http_client.clone()));
+    let authorization = client.get_authorization().await.unwrap();
+
+    assert_eq!(
+      authorization,
+      RegistryAuthorization {
+        registry: PUBLIC_REGISTRY.to_string(),
+        username: "AWS".to_string(),
+        password: "token".to_string(),
+      }
+    );
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    assert!(requests[0].uri().contains("us-east-1"));
+  }
+
+  #[tokio::test]
+  async fn test_get_repositories() {
+    let first_page = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{\"repositories\": [{\"repositoryName\": \"shop\"}], \"nextToken\": \"page-2\"}",
+      ))
+      .unwrap();
+    let second_page = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{\"repositories\": [{\"repositoryName\": \"blog\"}]}",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), first_page),
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), second_page),
+    ]);
+
+    let client = EcrPublicClient::new(&sdk_config_with(http_client.clone()));
+    let repositories = client.get_repositories().await.unwrap();
+
+    assert_eq!(
+      repositories
+        .iter()
+        .map(|repository| repository.repository_name().unwrap())
+        .collect::<Vec<_>>(),
+      ["shop", "blog"]
+    );
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let request: serde_json::Value =
+      serde_json::from_slice(requests[1].body().bytes().unwrap()).unwrap();
+    assert_eq!(request["nextToken"], "page-2");
+  }
+
+  #[tokio::test]
+  async fn test_image_exists() {
+    let found = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "{\"imageDetails\": [{\"repositoryName\": \"shop\", \"imageTags\": [\"1.1\"]}]}",
+      ))
+      .unwrap();
+    let not_found = http::Response::builder()
+      .status(400)
+      .header("x-amzn-errortype", "ImageNotFoundException")
+      .body(SdkBody::from(
+        "{\"__type\": \"ImageNotFoundException\", \"message\": \"not found\"}",
+      ))
+      .unwrap();
+    let http_client = StaticReplayClient::new(vec![
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), found),
+      ReplayEvent::new(HttpRequest::new(SdkBody::from("")), not_found),
+    ]);
+
+    let client = EcrPublicClient::new(&sdk_config_with(http_client.clone()));
+
+    assert!(client.image_exists("shop", "1.1").await.unwrap());
+    assert!(!client.image_exists("shop", "1.2").await.unwrap());
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let request: serde_json::Value =
+      serde_json::from_slice(requests[0].body().bytes().unwrap()).unwrap();
+    assert_eq!(request["repositoryName"], "shop");
+    assert_eq!(request["imageIds"][0]["imageTag"], "1.1");
+  }
+}
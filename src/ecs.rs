@@ -1,14 +1,18 @@
 use aws_config::SdkConfig;
 use aws_sdk_ecs::{
   Client,
+  operation::register_task_definition::builders::RegisterTaskDefinitionFluentBuilder,
   types::{
-    ContainerDefinition, DesiredStatus, LaunchType, NetworkConfiguration, Service, Session, Task,
-    TaskDefinition,
+    ContainerDefinition, DesiredStatus, LaunchType, NetworkConfiguration, Service, Session, Tag,
+    Task, TaskDefinition, TaskDefinitionField,
   },
 };
 
 use crate::errors::EcsHelperVarietyError;
 
+/// ECS list APIs return at most 100 items per page, we follow `nextToken` to get the rest.
+const MAX_RESULTS_PER_PAGE: i32 = 100;
+
 pub struct EcsClient {
   client: Client,
 }
@@ -21,31 +25,37 @@ impl EcsClient {
   }
 
   pub async fn get_clusters(&self) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
-    let response = self
+    let clusters = self
       .client
       .list_clusters()
-      .max_results(100)
+      .max_results(MAX_RESULTS_PER_PAGE)
+      .into_paginator()
+      .items()
       .send()
+      .try_collect()
       .await
-      .map_err(EcsHelperVarietyError::GetListClustersError)?;
+      .map_err(|error| EcsHelperVarietyError::GetListClustersError(Box::new(error)))?;
 
-    Ok(response.cluster_arns().to_vec())
+    Ok(clusters)
   }
 
   pub async fn get_services(
     &self,
     cluster_arn: &String,
   ) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
-    let response = self
+    let services = self
       .client
       .list_services()
       .cluster(cluster_arn)
-      .max_results(100)
+      .max_results(MAX_RESULTS_PER_PAGE)
+      .into_paginator()
+      .items()
       .send()
+      .try_collect()
       .await
-      .map_err(EcsHelperVarietyError::GetListServicesError)?;
+      .map_err(|error| EcsHelperVarietyError::GetListServicesError(Box::new(error)))?;
 
-    Ok(response.service_arns().to_vec())
+    Ok(services)
   }
 
   pub async fn get_tasks(
@@ -53,18 +63,21 @@ impl EcsClient {
     cluster_arn: &String,
     service_arn: &String,
   ) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
-    let response = self
+    let tasks = self
       .client
       .list_tasks()
       .cluster(cluster_arn)
       .service_name(service_arn)
       .desired_status(DesiredStatus::Running)
-      .max_results(100)
+      .max_results(MAX_RESULTS_PER_PAGE)
+      .into_paginator()
+      .items()
       .send()
+      .try_collect()
       .await
-      .map_err(EcsHelperVarietyError::GetListTasksError)?;
+      .map_err(|error| EcsHelperVarietyError::GetListTasksError(Box::new(error)))?;
 
-    Ok(response.task_arns().to_vec())
+    Ok(tasks)
   }
 
   pub async fn describe_service(
@@ -79,7 +92,7 @@ impl EcsClient {
       .services(service_arn)
       .send()
       .await
-      .map_err(EcsHelperVarietyError::DescribeServiceError)?;
+      .map_err(|error| EcsHelperVarietyError::DescribeServiceError(Box::new(error)))?;
 
     let [service] = response.services() else {
       return Err(EcsHelperVarietyError::NoServicesFound);
@@ -89,15 +102,18 @@ impl EcsClient {
   }
 
   pub async fn get_task_definitions(&self) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
-    let response = self
+    let task_definitions = self
       .client
       .list_task_definitions()
-      .max_results(100)
+      .max_results(MAX_RESULTS_PER_PAGE)
+      .into_paginator()
+      .items()
       .send()
+      .try_collect()
       .await
-      .map_err(EcsHelperVarietyError::GetListTaskDefinitionsError)?;
+      .map_err(|error| EcsHelperVarietyError::GetListTaskDefinitionsError(Box::new(error)))?;
 
-    Ok(response.task_definition_arns().to_vec())
+    Ok(task_definitions)
   }
 
   pub async fn describe_task(
@@ -112,7 +128,7 @@ impl EcsClient {
       .tasks(task_arn)
       .send()
       .await
-      .map_err(EcsHelperVarietyError::DescribeTaskError)?;
+      .map_err(|error| EcsHelperVarietyError::DescribeTaskError(Box::new(error)))?;
 
     let [task] = response.tasks() else {
       return Err(EcsHelperVarietyError::NoTasksFound);
@@ -131,7 +147,7 @@ impl EcsClient {
       .task_definition(task_definition_arn)
       .send()
       .await
-      .map_err(EcsHelperVarietyError::DescribeTaskDefinitionError)?;
+      .map_err(|error| EcsHelperVarietyError::DescribeTaskDefinitionError(Box::new(error)))?;
 
     let task_definition = response
       .task_definition()
@@ -140,48 +156,42 @@ impl EcsClient {
     Ok(task_definition.to_owned())
   }
 
-  pub async fn register_task_definition_from(
+  /// Describes task definition together with its tags, they are not part of `TaskDefinition`.
+  pub async fn describe_task_definition_with_tags(
     &self,
-    task_definition: &TaskDefinition,
-    container_definitions: Vec<ContainerDefinition>,
-  ) -> miette::Result<TaskDefinition, EcsHelperVarietyError> {
-    let mut request = self
+    task_definition_arn: &String,
+  ) -> miette::Result<(TaskDefinition, Vec<Tag>), EcsHelperVarietyError> {
+    let response = self
       .client
-      .register_task_definition()
-      .set_container_definitions(Some(container_definitions));
-
-    if let Some(execution_role_arn) = task_definition.execution_role_arn() {
-      request = request.execution_role_arn(execution_role_arn.to_owned());
-    }
-
-    if let Some(family) = task_definition.family() {
-      request = request.family(family.to_owned());
-    }
-
-    if let Some(memory) = task_definition.memory() {
-      request = request.memory(memory.to_owned());
-    }
-
-    if let Some(network_mode) = task_definition.network_mode() {
-      request = request.network_mode(network_mode.to_owned());
-    }
+      .describe_task_definition()
+      .task_definition(task_definition_arn)
+      .include(TaskDefinitionField::Tags)
+      .send()
+      .await
+      .map_err(|error| EcsHelperVarietyError::DescribeTaskDefinitionError(Box::new(error)))?;
 
-    if let Some(cpu) = task_definition.cpu() {
-      request = request.cpu(cpu.to_owned());
-    }
+    let task_definition = response
+      .task_definition()
+      .ok_or(EcsHelperVarietyError::ExtractTaskDefinitionError)?;
 
-    if let Some(requires_compatibilities) = task_definition.requires_compatibilities().first() {
-      request = request.requires_compatibilities(requires_compatibilities.to_owned());
-    }
+    Ok((task_definition.to_owned(), response.tags().to_vec()))
+  }
 
-    if let Some(role_arn) = task_definition.task_role_arn() {
-      request = request.task_role_arn(role_arn.to_owned());
-    }
+  /// Registers new revision of the task definition with the given container definitions,
+  /// all other fields are copied from the source task definition.
+  pub async fn register_task_definition_from(
+    &self,
+    task_definition: &TaskDefinition,
+    tags: &[Tag],
+    container_definitions: Vec<ContainerDefinition>,
+  ) -> miette::Result<TaskDefinition, EcsHelperVarietyError> {
+    let request =
+      self.register_task_definition_request(task_definition, tags, container_definitions);
 
     let response = request
       .send()
       .await
-      .map_err(EcsHelperVarietyError::RegisterTaskDefinitionError)?;
+      .map_err(|error| EcsHelperVarietyError::RegisterTaskDefinitionError(Box::new(error)))?;
 
     let task_definition = response
       .task_definition()
@@ -190,6 +200,37 @@ impl EcsClient {
     Ok(task_definition.to_owned())
   }
 
+  fn register_task_definition_request(
+    &self,
+    task_definition: &TaskDefinition,
+    tags: &[Tag],
+    container_definitions: Vec<ContainerDefinition>,
+  ) -> RegisterTaskDefinitionFluentBuilder {
+    let tags = (!tags.is_empty()).then(|| tags.to_vec());
+
+    self
+      .client
+      .register_task_definition()
+      .set_family(task_definition.family.clone())
+      .set_task_role_arn(task_definition.task_role_arn.clone())
+      .set_execution_role_arn(task_definition.execution_role_arn.clone())
+      .set_network_mode(task_definition.network_mode.clone())
+      .set_container_definitions(Some(container_definitions))
+      .set_volumes(task_definition.volumes.clone())
+      .set_placement_constraints(task_definition.placement_constraints.clone())
+      .set_requires_compatibilities(task_definition.requires_compatibilities.clone())
+      .set_cpu(task_definition.cpu.clone())
+      .set_memory(task_definition.memory.clone())
+      .set_tags(tags)
+      .set_pid_mode(task_definition.pid_mode.clone())
+      .set_ipc_mode(task_definition.ipc_mode.clone())
+      .set_proxy_configuration(task_definition.proxy_configuration.clone())
+      .set_inference_accelerators(task_definition.inference_accelerators.clone())
+      .set_ephemeral_storage(task_definition.ephemeral_storage.clone())
+      .set_runtime_platform(task_definition.runtime_platform.clone())
+      .set_enable_fault_injection(task_definition.enable_fault_injection)
+  }
+
   pub async fn execute_command(
     &self,
     cluster_arn: &String,
@@ -209,7 +250,7 @@ impl EcsClient {
     let response = execute_command_builder
       .send()
       .await
-      .map_err(EcsHelperVarietyError::ExecuteCommandError)?;
+      .map_err(|error| EcsHelperVarietyError::ExecuteCommandError(Box::new(error)))?;
 
     let session = response
       .session
@@ -239,7 +280,7 @@ impl EcsClient {
     let response = run_task_builder
       .send()
       .await
-      .map_err(EcsHelperVarietyError::RunTaskError)?;
+      .map_err(|error| EcsHelperVarietyError::RunTaskError(Box::new(error)))?;
 
     let [task] = response.tasks() else {
       return Err(EcsHelperVarietyError::NoTasksFound);
@@ -262,7 +303,7 @@ impl EcsClient {
       .cluster(cluster_arn)
       .send()
       .await
-      .map_err(EcsHelperVarietyError::UpdateServiceError)?;
+      .map_err(|error| EcsHelperVarietyError::UpdateServiceError(Box::new(error)))?;
 
     let service = response
       .service()
@@ -277,6 +318,7 @@ mod tests {
   use super::*;
   use aws_config::{BehaviorVersion, Region};
   use aws_sdk_ecr::config::{Credentials, SharedCredentialsProvider};
+  use aws_sdk_ecs::types::Compatibility;
   use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
   use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
   use aws_smithy_types::body::SdkBody;
@@ -320,6 +362,68 @@ mod tests {
     );
   }
 
+  #[tokio::test]
+  async fn test_get_clusters_with_multiple_pages() {
+    let first_page = ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      http::Response::builder()
+        .status(200)
+        .body(SdkBody::from(
+          r#"
+          {
+            "clusterArns": [
+              "arn:aws:ecs:us-east-1:123456789012:cluster/first"
+            ],
+            "nextToken": "page-2"
+          }
+        "#,
+        ))
+        .unwrap(),
+    );
+    let second_page = ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      http::Response::builder()
+        .status(200)
+        .body(SdkBody::from(
+          r#"
+          {
+            "clusterArns": [
+              "arn:aws:ecs:us-east-1:123456789012:cluster/second"
+            ]
+          }
+        "#,
+        ))
+        .unwrap(),
+    );
+
+    let http_client = StaticReplayClient::new(vec![first_page, second_page]);
+
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client.clone())
+      .build();
+    let ecs_client = EcsClient::new(&sdk_config);
+
+    let clusters = ecs_client.get_clusters().await.unwrap();
+
+    assert_eq!(
+      clusters,
+      vec![
+        "arn:aws:ecs:us-east-1:123456789012:cluster/first",
+        "arn:aws:ecs:us-east-1:123456789012:cluster/second"
+      ]
+    );
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    assert_eq!(requests.len(), 2);
+    let second_request = std::str::from_utf8(requests[1].body().bytes().unwrap()).unwrap();
+    assert!(second_request.contains(r#""nextToken":"page-2""#));
+  }
+
   #[tokio::test]
   async fn get_services() {
     let request = HttpRequest::new(SdkBody::from(""));
@@ -355,6 +459,54 @@ mod tests {
     assert_eq!(clusters.len(), 0);
   }
 
+  #[tokio::test]
+  async fn get_services_with_multiple_pages() {
+    let pages = (1..=3)
+      .map(|page| {
+        let next_token = if page < 3 {
+          format!(r#", "nextToken": "page-{}""#, page + 1)
+        } else {
+          String::new()
+        };
+
+        let body = format!(
+          r#"{{ "serviceArns": ["arn:aws:ecs:us-east-1:123456789012:service/default/service-{page}"]{next_token} }}"#
+        );
+
+        ReplayEvent::new(
+          HttpRequest::new(SdkBody::from("")),
+          http::Response::builder()
+            .status(200)
+            .body(SdkBody::from(body))
+            .unwrap(),
+        )
+      })
+      .collect::<Vec<_>>();
+
+    let http_client = StaticReplayClient::new(pages);
+
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client.clone())
+      .build();
+    let ecs_client = EcsClient::new(&sdk_config);
+
+    let cluster_arn = "arn:aws:ecs:us-east-1:123456789012:cluster/default".to_owned();
+
+    let services = ecs_client.get_services(&cluster_arn).await.unwrap();
+
+    assert_eq!(services.len(), 3);
+    assert_eq!(
+      services.last().unwrap(),
+      "arn:aws:ecs:us-east-1:123456789012:service/default/service-3"
+    );
+    assert_eq!(http_client.actual_requests().count(), 3);
+  }
+
   #[tokio::test]
   async fn get_tasks() {
     let request = HttpRequest::new(SdkBody::from(""));
@@ -435,6 +587,120 @@ mod tests {
     );
   }
 
+  #[tokio::test]
+  async fn get_tasks_with_multiple_pages() {
+    let first_page = ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      http::Response::builder()
+        .status(200)
+        .body(SdkBody::from(
+          r#"
+          {
+            "taskArns": [
+              "arn:aws:ecs:us-east-1:123456789012:task/cluster/first"
+            ],
+            "nextToken": "page-2"
+          }
+        "#,
+        ))
+        .unwrap(),
+    );
+    let second_page = ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      http::Response::builder()
+        .status(200)
+        .body(SdkBody::from(
+          r#"
+          {
+            "taskArns": [
+              "arn:aws:ecs:us-east-1:123456789012:task/cluster/second"
+            ]
+          }
+        "#,
+        ))
+        .unwrap(),
+    );
+
+    let http_client = StaticReplayClient::new(vec![first_page, second_page]);
+
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client)
+      .build();
+    let ecs_client = EcsClient::new(&sdk_config);
+
+    let cluster_arn = "arn:aws:ecs:us-east-1:123456789012:cluster/default".to_owned();
+    let service_arn = "arn:aws:ecs:us-east-1:123456789012:service/default/web".to_owned();
+
+    let tasks = ecs_client
+      .get_tasks(&cluster_arn, &service_arn)
+      .await
+      .unwrap();
+
+    assert_eq!(tasks.len(), 2);
+  }
+
+  #[tokio::test]
+  async fn test_get_task_definitions_with_multiple_pages() {
+    let first_page = ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      http::Response::builder()
+        .status(200)
+        .body(SdkBody::from(
+          r#"
+          {
+            "taskDefinitionArns": [
+              "arn:aws:ecs:us-east-1:123456789012:task-definition/nginx:1"
+            ],
+            "nextToken": "page-2"
+          }
+        "#,
+        ))
+        .unwrap(),
+    );
+    let second_page = ReplayEvent::new(
+      HttpRequest::new(SdkBody::from("")),
+      http::Response::builder()
+        .status(200)
+        .body(SdkBody::from(
+          r#"
+          {
+            "taskDefinitionArns": [
+              "arn:aws:ecs:us-east-1:123456789012:task-definition/nginx:2"
+            ]
+          }
+        "#,
+        ))
+        .unwrap(),
+    );
+
+    let http_client = StaticReplayClient::new(vec![first_page, second_page]);
+
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client)
+      .build();
+    let ecs_client = EcsClient::new(&sdk_config);
+
+    let task_definitions = ecs_client.get_task_definitions().await.unwrap();
+
+    assert_eq!(
+      task_definitions,
+      vec![
+        "arn:aws:ecs:us-east-1:123456789012:task-definition/nginx:1",
+        "arn:aws:ecs:us-east-1:123456789012:task-definition/nginx:2"
+      ]
+    );
+  }
+
   #[tokio::test]
   async fn test_describe_task() {
     let request = HttpRequest::new(SdkBody::from(""));
@@ -536,6 +802,194 @@ mod tests {
     assert!(task_definition.task_role_arn().is_none());
   }
 
+  #[tokio::test]
+  async fn test_describe_task_definition_with_tags() {
+    let request = HttpRequest::new(SdkBody::from(""));
+
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(
+        "
+        {
+          \"taskDefinition\": {
+            \"taskDefinitionArn\": \"arn:aws:ecs:us-east-1:123456789012:task-definition/nginx:1\"
+          },
+          \"tags\": [{ \"key\": \"team\", \"value\": \"platform\" }]
+        }
+      ",
+      ))
+      .unwrap();
+    let page = ReplayEvent::new(request, response);
+
+    let http_client = StaticReplayClient::new(vec![page]);
+
+    let credentials = SharedCredentialsProvider::new(Credentials::for_tests_with_session_token());
+
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(credentials)
+      .http_client(http_client.clone())
+      .build();
+
+    let ecs_client = EcsClient::new(&sdk_config);
+    let task_definition_arn =
+      "arn:aws:ecs:us-east-1:123456789012:task-definition/nginx:1".to_owned();
+    let (task_definition, tags) = ecs_client
+      .describe_task_definition_with_tags(&task_definition_arn)
+      .await
+      .unwrap();
+
+    assert_eq!(
+      task_definition.task_definition_arn(),
+      Some(task_definition_arn.as_str())
+    );
+    assert_eq!(
+      tags,
+      vec![Tag::builder().key("team").value("platform").build()]
+    );
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    let body = std::str::from_utf8(requests[0].body().bytes().unwrap()).unwrap();
+    assert!(body.contains("\"include\":[\"TAGS\"]"));
+  }
+
+  #[test]
+  fn test_register_task_definition_request_copies_all_fields() {
+    use aws_sdk_ecs::types::{
+      CpuArchitecture, EphemeralStorage, HostVolumeProperties, InferenceAccelerator, IpcMode,
+      NetworkMode, OsFamily, PidMode, ProxyConfiguration, ProxyConfigurationType, RuntimePlatform,
+      TaskDefinitionPlacementConstraint, TaskDefinitionPlacementConstraintType, Volume,
+    };
+
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .build();
+    let ecs_client = EcsClient::new(&sdk_config);
+
+    let task_definition = TaskDefinition::builder()
+      .task_definition_arn("arn:aws:ecs:us-east-1:123456789012:task-definition/web:1")
+      .family("web")
+      .revision(1)
+      .task_role_arn("arn:aws:iam::123456789012:role/web-task")
+      .execution_role_arn("arn:aws:iam::123456789012:role/web-execution")
+      .network_mode(NetworkMode::Awsvpc)
+      .volumes(
+        Volume::builder()
+          .name("data")
+          .host(HostVolumeProperties::builder().source_path("/data").build())
+          .build(),
+      )
+      .placement_constraints(
+        TaskDefinitionPlacementConstraint::builder()
+          .r#type(TaskDefinitionPlacementConstraintType::MemberOf)
+          .expression("attribute:ecs.availability-zone in [us-east-1a]")
+          .build(),
+      )
+      .requires_compatibilities(Compatibility::Ec2)
+      .requires_compatibilities(Compatibility::Fargate)
+      .cpu("256")
+      .memory("512")
+      .pid_mode(PidMode::Task)
+      .ipc_mode(IpcMode::Task)
+      .proxy_configuration(
+        ProxyConfiguration::builder()
+          .r#type(ProxyConfigurationType::Appmesh)
+          .container_name("envoy")
+          .build()
+          .unwrap(),
+      )
+      .inference_accelerators(
+        InferenceAccelerator::builder()
+          .device_name("device")
+          .device_type("eia2.medium")
+          .build()
+          .unwrap(),
+      )
+      .ephemeral_storage(EphemeralStorage::builder().size_in_gib(50).build())
+      .runtime_platform(
+        RuntimePlatform::builder()
+          .cpu_architecture(CpuArchitecture::Arm64)
+          .operating_system_family(OsFamily::Linux)
+          .build(),
+      )
+      .enable_fault_injection(true)
+      .build();
+    let tags = vec![Tag::builder().key("team").value("platform").build()];
+    let container_definitions = vec![ContainerDefinition::builder().name("web").build()];
+
+    let request = ecs_client.register_task_definition_request(
+      &task_definition,
+      &tags,
+      container_definitions.clone(),
+    );
+    let input = request.as_input();
+
+    assert_eq!(input.get_family(), &task_definition.family);
+    assert_eq!(input.get_task_role_arn(), &task_definition.task_role_arn);
+    assert_eq!(
+      input.get_execution_role_arn(),
+      &task_definition.execution_role_arn
+    );
+    assert_eq!(input.get_network_mode(), &task_definition.network_mode);
+    assert_eq!(
+      input.get_container_definitions(),
+      &Some(container_definitions)
+    );
+    assert_eq!(input.get_volumes(), &task_definition.volumes);
+    assert_eq!(
+      input.get_placement_constraints(),
+      &task_definition.placement_constraints
+    );
+    assert_eq!(
+      input.get_requires_compatibilities(),
+      &task_definition.requires_compatibilities
+    );
+    assert_eq!(input.get_cpu(), &task_definition.cpu);
+    assert_eq!(input.get_memory(), &task_definition.memory);
+    assert_eq!(input.get_tags(), &Some(tags));
+    assert_eq!(input.get_pid_mode(), &task_definition.pid_mode);
+    assert_eq!(input.get_ipc_mode(), &task_definition.ipc_mode);
+    assert_eq!(
+      input.get_proxy_configuration(),
+      &task_definition.proxy_configuration
+    );
+    assert_eq!(
+      input.get_inference_accelerators(),
+      &task_definition.inference_accelerators
+    );
+    assert_eq!(
+      input.get_ephemeral_storage(),
+      &task_definition.ephemeral_storage
+    );
+    assert_eq!(
+      input.get_runtime_platform(),
+      &task_definition.runtime_platform
+    );
+    assert_eq!(
+      input.get_enable_fault_injection(),
+      &task_definition.enable_fault_injection
+    );
+  }
+
+  #[test]
+  fn test_register_task_definition_request_without_tags() {
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .build();
+    let ecs_client = EcsClient::new(&sdk_config);
+
+    let task_definition = TaskDefinition::builder().family("web").build();
+
+    let request = ecs_client.register_task_definition_request(&task_definition, &[], vec![]);
+
+    // empty list of tags is rejected by the API
+    assert_eq!(request.as_input().get_tags(), &None);
+    assert_eq!(request.as_input().get_volumes(), &None);
+  }
+
   #[tokio::test]
   async fn test_update_service() {
     let request = HttpRequest::new(SdkBody::from(""));
@@ -1,10 +1,20 @@
+use aws_sdk_cloudwatchlogs::operation::{
+  filter_log_events::FilterLogEventsError, get_log_events::GetLogEventsError,
+};
 use aws_sdk_ecr::{
   error::SdkError,
   operation::{
-    describe_images::DescribeImagesError, describe_repositories::DescribeRepositoriesError,
-    get_authorization_token::GetAuthorizationTokenError,
+    batch_get_image::BatchGetImageError, describe_images::DescribeImagesError,
+    describe_repositories::DescribeRepositoriesError,
+    get_authorization_token::GetAuthorizationTokenError, put_image::PutImageError,
+    start_image_scan::StartImageScanError,
   },
 };
+use aws_sdk_ecrpublic::operation::{
+  describe_images::DescribeImagesError as DescribePublicImagesError,
+  describe_repositories::DescribeRepositoriesError as DescribePublicRepositoriesError,
+  get_authorization_token::GetAuthorizationTokenError as GetPublicAuthorizationTokenError,
+};
 use aws_sdk_ecs::operation::{
   describe_services::DescribeServicesError, describe_task_definition::DescribeTaskDefinitionError,
   describe_tasks::DescribeTasksError, execute_command::ExecuteCommandError,
@@ -29,6 +39,25 @@ pub enum EcsHelperVarietyError {
   #[diagnostic(code(ecs_helper::config::extract_environment_error))]
   ExtractEnvironmentError(String),
 
+  #[error("Invalid branch pattern {0}")]
+  #[diagnostic(code(ecs_helper::config::invalid_branch_pattern))]
+  InvalidBranchPattern(String),
+
+  #[error("Failed to read config file {0}")]
+  #[diagnostic(code(ecs_helper::config::read_config_file_error))]
+  ReadConfigFileError(String),
+
+  #[error("Failed to parse config file:\n{0}")]
+  #[diagnostic(code(ecs_helper::config::parse_config_file_error))]
+  ParseConfigFileError(#[from] toml::de::Error),
+
+  #[error("Missing required option `{0}`")]
+  #[diagnostic(
+    code(ecs_helper::config::missing_required_option),
+    help("Pass it as a CLI flag, an environment variable or set it in .ecs_helpers.toml")
+  )]
+  MissingRequiredOption(String),
+
   #[error(transparent)]
   #[diagnostic(code(ecs_helper::write::io_error))]
   IoError(#[from] std::io::Error),
@@ -38,12 +67,15 @@ pub enum EcsHelperVarietyError {
   ReedOutputError(std::io::Error),
 
   #[error("Failed to get region")]
-  #[diagnostic(code(ecs_helper::auth::get_region_error))]
+  #[diagnostic(
+    code(ecs_helper::auth::get_region_error),
+    help("Pass --region or set AWS_REGION, or configure the region of the AWS profile")
+  )]
   GetRegionError,
 
   #[error("Failed to get token:\n{0}")]
   #[diagnostic(code(ecs_helper::auth::get_token_error))]
-  GetTokenError(#[from] SdkError<GetAuthorizationTokenError>),
+  GetTokenError(#[from] Box<SdkError<GetAuthorizationTokenError>>),
 
   #[error("Failed to extract token")]
   #[diagnostic(code(ecs_helper::auth::extract_token_error))]
@@ -61,21 +93,111 @@ pub enum EcsHelperVarietyError {
   #[diagnostic(code(ecs_helper::auth::json_serialization_error))]
   JsonSerializationError(#[from] serde_json::Error),
 
+  #[error("Invalid registry {0}")]
+  #[diagnostic(
+    code(ecs_helper::login::invalid_registry),
+    help("Pass the registry as account_id, account_id:region or the registry host")
+  )]
+  InvalidRegistry(String),
+
+  #[error("Registry {0} is not an ECR registry")]
+  #[diagnostic(
+    code(ecs_helper::credential_helper::not_ecr_registry),
+    help("The credential helper only serves private ECR registries, use credHelpers for them")
+  )]
+  NotEcrRegistry(String),
+
+  #[error("Failed to update docker config: {0}")]
+  #[diagnostic(code(ecs_helper::login::docker_config_error))]
+  DockerConfigError(String),
+
   #[error("Login command was failed\n{0}")]
   #[diagnostic(code(ecs_helper::login::login_failed))]
   LoginFailed(String),
 
   #[error("Failed to describe repositories:\n{0}")]
   #[diagnostic(code(ecs_helper::ecr::describe_repositories_error))]
-  DescribeRepositoriesError(#[from] SdkError<DescribeRepositoriesError>),
+  DescribeRepositoriesError(#[from] Box<SdkError<DescribeRepositoriesError>>),
+
+  #[error("Failed to get ECR Public token:\n{0}")]
+  #[diagnostic(code(ecs_helper::auth::get_public_token_error))]
+  GetPublicTokenError(#[from] Box<SdkError<GetPublicAuthorizationTokenError>>),
+
+  #[error("Failed to describe ECR Public repositories:\n{0}")]
+  #[diagnostic(code(ecs_helper::ecr_public::describe_repositories_error))]
+  DescribePublicRepositoriesError(#[from] Box<SdkError<DescribePublicRepositoriesError>>),
+
+  #[error("Failed to describe ECR Public images:\n{0}")]
+  #[diagnostic(code(ecs_helper::ecr_public::describe_images_error))]
+  DescribePublicImagesError(#[from] Box<SdkError<DescribePublicImagesError>>),
 
   #[error("Describe images was failed:\n{0}")]
   #[diagnostic(code(ecs_helper::ecr::describe_images_error))]
-  DescribeImagesError(#[from] SdkError<DescribeImagesError>),
+  DescribeImagesError(#[from] Box<SdkError<DescribeImagesError>>),
+
+  #[error("Failed to get image manifest:\n{0}")]
+  #[diagnostic(code(ecs_helper::ecr::batch_get_image_error))]
+  BatchGetImageError(#[from] Box<SdkError<BatchGetImageError>>),
+
+  #[error("Failed to tag image:\n{0}")]
+  #[diagnostic(code(ecs_helper::ecr::put_image_error))]
+  PutImageError(#[from] Box<SdkError<PutImageError>>),
+
+  #[error("Failed to start image scan:\n{0}")]
+  #[diagnostic(
+    code(ecs_helper::ecr::start_image_scan_error),
+    help("Enable scan on push for the repository or allow ecr:StartImageScan")
+  )]
+  StartImageScanError(#[from] Box<SdkError<StartImageScanError>>),
+
+  #[error("Image scan of {image} failed: {reason}")]
+  #[diagnostic(code(ecs_helper::ecr::image_scan_failed))]
+  ImageScanFailed { image: String, reason: String },
+
+  #[error("Image scan results were not available in {0} seconds")]
+  #[diagnostic(
+    code(ecs_helper::ecr::image_scan_timeout),
+    help("Check that scan on push is enabled for the repository")
+  )]
+  ImageScanTimeout(u64),
+
+  #[error("Image {image} has too many vulnerabilities: {findings}")]
+  #[diagnostic(code(ecs_helper::ecr::vulnerable_image))]
+  VulnerableImage { image: String, findings: String },
+
+  #[error("Invalid tag template {template}: {reason}")]
+  #[diagnostic(
+    code(ecs_helper::build_and_push::invalid_tag_template),
+    help(
+      "Available placeholders are {{branch}}, {{environment}}, {{sha}}, {{short_sha}}, {{semver}} and {{date}}"
+    )
+  )]
+  InvalidTagTemplate { template: String, reason: String },
+
+  #[error("Invalid image override {0}")]
+  #[diagnostic(code(ecs_helper::ecr::invalid_image_override))]
+  InvalidImageOverride(String),
+
+  #[error("Image overrides for unknown containers: {containers}")]
+  #[diagnostic(
+    code(ecs_helper::ecr::unknown_image_override_containers),
+    help("Containers of the task definition are {available}")
+  )]
+  UnknownImageOverrideContainers {
+    containers: String,
+    available: String,
+  },
+
+  #[error("Image {image} not found in ECR repository {repository}")]
+  #[diagnostic(
+    code(ecs_helper::ecr::image_not_found),
+    help("Build and push the image with build_and_push before deploying it")
+  )]
+  ImageNotFound { repository: String, image: String },
 
   #[error("Failed to terminate session:\n{0}")]
   #[diagnostic(code(ecs_helper::ssm::terninate_session_error))]
-  TerminateSessionError(#[from] SdkError<TerminateSessionError>),
+  TerminateSessionError(#[from] Box<SdkError<TerminateSessionError>>),
 
   #[error("Failed to extract image")]
   #[diagnostic(code(ecs_helper::ecr::extract_image_error))]
@@ -83,7 +205,7 @@ pub enum EcsHelperVarietyError {
 
   #[error("Failed to describe task definition:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::describe_task_definition_error))]
-  DescribeTaskDefinitionError(#[from] SdkError<DescribeTaskDefinitionError>),
+  DescribeTaskDefinitionError(#[from] Box<SdkError<DescribeTaskDefinitionError>>),
 
   #[error("Failed to extract description")]
   #[diagnostic(code(ecs_helper::ecs::extract_description_error))]
@@ -91,15 +213,15 @@ pub enum EcsHelperVarietyError {
 
   #[error("Failed to get list task definitions:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::get_list_task_definitions_error))]
-  GetListTaskDefinitionsError(#[from] SdkError<ListTaskDefinitionsError>),
+  GetListTaskDefinitionsError(#[from] Box<SdkError<ListTaskDefinitionsError>>),
 
   #[error("Failed to register task definition:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::no_task_definitions_found))]
-  RegisterTaskDefinitionError(#[from] SdkError<RegisterTaskDefinitionError>),
+  RegisterTaskDefinitionError(#[from] Box<SdkError<RegisterTaskDefinitionError>>),
 
   #[error("Failed to get list clusters:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::get_list_clusters_error))]
-  GetListClustersError(#[from] SdkError<ListClustersError>),
+  GetListClustersError(#[from] Box<SdkError<ListClustersError>>),
 
   #[error("No clusters found")]
   #[diagnostic(code(ecs_helper::ecs::no_clusters_found))]
@@ -123,11 +245,11 @@ pub enum EcsHelperVarietyError {
 
   #[error("Failed to run task:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::run_task_error))]
-  RunTaskError(#[from] SdkError<RunTaskError>),
+  RunTaskError(#[from] Box<SdkError<RunTaskError>>),
 
   #[error("Failed to execute command inside container:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::execute_command_error))]
-  ExecuteCommandError(#[from] SdkError<ExecuteCommandError>),
+  ExecuteCommandError(#[from] Box<SdkError<ExecuteCommandError>>),
 
   #[error("No tasks found")]
   #[diagnostic(code(ecs_helper::ecs::no_tasks_found))]
@@ -135,11 +257,19 @@ pub enum EcsHelperVarietyError {
 
   #[error("Failed to get list services:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::get_list_services_error))]
-  GetListServicesError(#[from] SdkError<ListServicesError>),
+  GetListServicesError(#[from] Box<SdkError<ListServicesError>>),
 
   #[error("Failed to describe services:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::describe_services_error))]
-  DescribeServiceError(#[from] SdkError<DescribeServicesError>),
+  DescribeServiceError(#[from] Box<SdkError<DescribeServicesError>>),
+
+  #[error("Invalid service pattern {0}")]
+  #[diagnostic(code(ecs_helper::ecs::invalid_service_pattern))]
+  InvalidServicePattern(String),
+
+  #[error("Deployment of {0} failed")]
+  #[diagnostic(code(ecs_helper::ecs::deployments_failed))]
+  DeploymentsFailed(String),
 
   #[error("No services found")]
   #[diagnostic(code(ecs_helper::ecs::no_services_found))]
@@ -147,23 +277,67 @@ pub enum EcsHelperVarietyError {
 
   #[error("Failed to get list tasks:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::get_list_tasks_error))]
-  GetListTasksError(#[from] SdkError<ListTasksError>),
+  GetListTasksError(#[from] Box<SdkError<ListTasksError>>),
 
   #[error("Failed to describe task:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::describe_task_error))]
-  DescribeTaskError(#[from] SdkError<DescribeTasksError>),
+  DescribeTaskError(#[from] Box<SdkError<DescribeTasksError>>),
 
   #[error("Task run timeout ({0})")]
   #[diagnostic(code(ecs_helper::ecs::wait_task_timeout_error))]
   WaitTaskTimeoutError(u64),
 
+  #[error("Deployment {deployment_id} failed: {reason}")]
+  #[diagnostic(code(ecs_helper::ecs::deployment_failed))]
+  DeploymentFailed {
+    deployment_id: String,
+    reason: String,
+  },
+
+  #[error("Service has no primary deployment")]
+  #[diagnostic(code(ecs_helper::ecs::no_primary_deployment))]
+  NoPrimaryDeployment,
+
+  #[error("Deployment failed and service was rolled back to {task_definition_arn}:\n{reason}")]
+  #[diagnostic(code(ecs_helper::ecs::deployment_rolled_back))]
+  DeploymentRolledBack {
+    task_definition_arn: String,
+    reason: String,
+  },
+
+  #[error(
+    "Deployment failed and rollback to {task_definition_arn} failed too:\n{reason}\nRollback: {rollback_reason}"
+  )]
+  #[diagnostic(code(ecs_helper::ecs::rollback_failed))]
+  RollbackFailed {
+    task_definition_arn: String,
+    reason: String,
+    rollback_reason: String,
+  },
+
   #[error("Task {task_arn} was failed with code {code}")]
   #[diagnostic(code(ecs_helper::ecs::task_was_failed))]
   TaskWasFailed { task_arn: String, code: i32 },
 
+  #[error("Failed to get log events:\n{0}")]
+  #[diagnostic(code(ecs_helper::cloudwatch_logs::get_log_events_error))]
+  GetLogEventsError(#[from] Box<SdkError<GetLogEventsError>>),
+
+  #[error("Failed to filter log events:\n{0}")]
+  #[diagnostic(code(ecs_helper::cloudwatch_logs::filter_log_events_error))]
+  FilterLogEventsError(#[from] Box<SdkError<FilterLogEventsError>>),
+
+  #[error("Invalid duration {0}, expected a number followed by s, m, h or d")]
+  #[diagnostic(code(ecs_helper::cloudwatch_logs::invalid_duration))]
+  InvalidDuration(String),
+
+  #[error("No awslogs log configuration found for containers of the service")]
+  #[diagnostic(code(ecs_helper::cloudwatch_logs::no_log_configuration))]
+  NoLogConfiguration,
+
   #[error("Failed to ssm get parameters:\n{0}")]
   #[diagnostic(code(ecs_helper::ssm::get_ssm_parameters_error))]
-  GetSSMParametersError(#[from] SdkError<GetParametersError>),
+  GetSSMParametersError(#[from] Box<SdkError<GetParametersError>>),
 
   #[error("No ENV secrets to export. Please pass ENV variables names using -n")]
   #[diagnostic(code(ecs_helper::ssm::no_env_variables_to_export))]
@@ -177,6 +351,20 @@ pub enum EcsHelperVarietyError {
   #[diagnostic(code(ecs_helper::ecr::multiple_repositories_found))]
   MultipleRepositoriesFound(String),
 
+  #[error("{engine} doesn't support {operation}")]
+  #[diagnostic(
+    code(ecs_helper::build_and_push::unsupported_container_engine),
+    help("Select another engine with --engine or CONTAINER_ENGINE")
+  )]
+  UnsupportedContainerEngine { engine: String, operation: String },
+
+  #[error("buildx builder with the {driver} driver doesn't support {feature}")]
+  #[diagnostic(
+    code(ecs_helper::build_and_push::unsupported_buildx_driver),
+    help("Create a builder with `docker buildx create --use --driver docker-container`")
+  )]
+  UnsupportedBuildxDriver { driver: String, feature: String },
+
   #[error("Failed to pull image:\n{0}")]
   #[diagnostic(code(ecs_helper::docker::pull_image_error))]
   PullImageError(String),
@@ -189,13 +377,9 @@ pub enum EcsHelperVarietyError {
   #[diagnostic(code(ecs_helper::docker::push_image_error))]
   PushImageError(String),
 
-  #[error("Failed to create image tag:\n{0}")]
-  #[diagnostic(code(ecs_helper::docker::create_image_tag_error))]
-  ContainerDefinitionImageError(String),
-
   #[error("Failed to update service:\n{0}")]
   #[diagnostic(code(ecs_helper::ecs::update_service_error))]
-  UpdateServiceError(#[from] SdkError<UpdateServiceError>),
+  UpdateServiceError(#[from] Box<SdkError<UpdateServiceError>>),
 
   #[error("Failed to find container\n")]
   #[diagnostic(code(ecs_helper::ecs::cannot_find_container))]
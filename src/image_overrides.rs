@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use crate::errors::EcsHelperVarietyError;
+
+/// Image of a single container which takes precedence over the deployed version.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageOverride {
+  /// Tag applied to the current repository of the container.
+  Tag(String),
+  /// Full image reference used as is.
+  Image(String),
+}
+
+impl ImageOverride {
+  /// Value is a full image reference when it contains a registry, repository path, tag or digest,
+  /// otherwise it is a tag.
+  pub fn parse(value: &str) -> Self {
+    if value.contains(['/', ':', '@']) {
+      ImageOverride::Image(value.to_string())
+    } else {
+      ImageOverride::Tag(value.to_string())
+    }
+  }
+}
+
+/// Image overrides by container name, passed as `container=tag` or `container=image`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageOverrides {
+  overrides: HashMap<String, ImageOverride>,
+}
+
+impl ImageOverrides {
+  pub fn parse(overrides: &[String]) -> miette::Result<Self, EcsHelperVarietyError> {
+    let overrides = overrides
+      .iter()
+      .map(|image_override| {
+        let (container, value) = image_override
+          .split_once('=')
+          .map(|(container, value)| (container.trim(), value.trim()))
+          .filter(|(container, value)| !container.is_empty() && !value.is_empty())
+          .ok_or_else(|| {
+            EcsHelperVarietyError::InvalidImageOverride(format!(
+              "{image_override}: expected container=tag or container=image"
+            ))
+          })?;
+
+        Ok((container.to_string(), ImageOverride::parse(value)))
+      })
+      .collect::<miette::Result<_, EcsHelperVarietyError>>()?;
+
+    Ok(Self { overrides })
+  }
+
+  pub fn get(&self, container: &str) -> Option<&ImageOverride> {
+    self.overrides.get(container)
+  }
+
+  /// Fails when an override names a container which is not in the task definition,
+  /// e.g. a typo would silently deploy the version instead of the overridden image.
+  pub fn check_containers(&self, containers: &[&str]) -> miette::Result<(), EcsHelperVarietyError> {
+    let mut unknown = self
+      .overrides
+      .keys()
+      .filter(|container| !containers.contains(&container.as_str()))
+      .map(String::as_str)
+      .collect::<Vec<_>>();
+
+    if unknown.is_empty() {
+      return Ok(());
+    }
+
+    unknown.sort();
+
+    Err(EcsHelperVarietyError::UnknownImageOverrideContainers {
+      containers: unknown.join(", "),
+      available: containers.join(", "),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_image_override() {
+    assert_eq!(
+      ImageOverride::parse("3f2a1bc"),
+      ImageOverride::Tag("3f2a1bc".to_string())
+    );
+    assert_eq!(
+      ImageOverride::parse("nginx:1.27"),
+      ImageOverride::Image("nginx:1.27".to_string())
+    );
+    assert_eq!(
+      ImageOverride::parse("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop"),
+      ImageOverride::Image("123456789012.dkr.ecr.us-east-1.amazonaws.com/shop".to_string())
+    );
+  }
+
+  #[test]
+  fn test_parse_image_overrides() {
+    let overrides =
+      ImageOverrides::parse(&["web=3f2a1bc".to_string(), "nginx = nginx:1.27".to_string()])
+        .unwrap();
+
+    assert_eq!(
+      overrides.get("web"),
+      Some(&ImageOverride::Tag("3f2a1bc".to_string()))
+    );
+    assert_eq!(
+      overrides.get("nginx"),
+      Some(&ImageOverride::Image("nginx:1.27".to_string()))
+    );
+    assert_eq!(overrides.get("worker"), None);
+  }
+
+  #[test]
+  fn test_parse_invalid_image_overrides() {
+    for image_override in ["web", "=3f2a1bc", "web="] {
+      assert!(matches!(
+        ImageOverrides::parse(&[image_override.to_string()]),
+        Err(EcsHelperVarietyError::InvalidImageOverride(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn test_check_containers() {
+    let overrides = ImageOverrides::parse(&[
+      "web=3f2a1bc".to_string(),
+      "wrker=3f2a1bc".to_string(),
+      "ngnix=nginx:1.27".to_string(),
+    ])
+    .unwrap();
+
+    assert!(ImageOverrides::default().check_containers(&["web"]).is_ok());
+    assert!(
+      ImageOverrides::parse(&["web=3f2a1bc".to_string()])
+        .unwrap()
+        .check_containers(&["web", "nginx"])
+        .is_ok()
+    );
+    assert!(matches!(
+      overrides.check_containers(&["web", "nginx"]),
+      Err(EcsHelperVarietyError::UnknownImageOverrideContainers { containers, available })
+        if containers == "ngnix, wrker" && available == "web, nginx"
+    ));
+  }
+}
@@ -0,0 +1,420 @@
+use std::time::Duration;
+
+use aws_sdk_ecr::types::{
+  FindingSeverity, ImageDetail, ImageIdentifier, ImageScanFindingsSummary, ScanStatus,
+};
+
+use crate::{
+  args::{ImageScanArguments, ScanSeverity},
+  ecr::EcrClient,
+  errors::EcsHelperVarietyError,
+  manifest_list,
+};
+
+const DEFAULT_STEP: u64 = 5;
+
+const SEVERITIES: [(ScanSeverity, FindingSeverity); 6] = [
+  (ScanSeverity::Critical, FindingSeverity::Critical),
+  (ScanSeverity::High, FindingSeverity::High),
+  (ScanSeverity::Medium, FindingSeverity::Medium),
+  (ScanSeverity::Low, FindingSeverity::Low),
+  (ScanSeverity::Informational, FindingSeverity::Informational),
+  (ScanSeverity::Undefined, FindingSeverity::Undefined),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum ScanProgress {
+  InProgress,
+  Completed,
+}
+
+/// Limit of the image scan findings, images which exceed it must not be deployed.
+#[derive(Debug, Clone)]
+pub struct ScanGate {
+  severity: ScanSeverity,
+  max_findings: i32,
+  timeout: u64,
+}
+
+impl ScanGate {
+  /// Returns `None` when the scan severity is not set and images should not be checked.
+  pub fn from_args(args: &ImageScanArguments) -> Option<Self> {
+    Some(Self {
+      severity: args.scan_severity?,
+      max_findings: args.scan_max_findings,
+      timeout: args.scan_timeout,
+    })
+  }
+
+  /// Fails when the number of findings of the gate severity or higher exceeds the limit.
+  pub fn check(
+    &self,
+    image: &str,
+    summary: Option<&ImageScanFindingsSummary>,
+  ) -> miette::Result<(), EcsHelperVarietyError> {
+    let findings = SEVERITIES
+      .iter()
+      .filter(|(severity, _)| *severity <= self.severity)
+      .map(|(_, finding_severity)| finding_count(summary, finding_severity))
+      .sum::<i32>();
+
+    if findings > self.max_findings {
+      return Err(EcsHelperVarietyError::VulnerableImage {
+        image: image.to_string(),
+        findings: format_finding_counts(summary),
+      });
+    }
+
+    Ok(())
+  }
+}
+
+fn finding_count(
+  summary: Option<&ImageScanFindingsSummary>,
+  finding_severity: &FindingSeverity,
+) -> i32 {
+  summary
+    .and_then(|summary| summary.finding_severity_counts())
+    .and_then(|counts| counts.get(finding_severity))
+    .copied()
+    .unwrap_or_default()
+}
+
+/// Formats finding counts from the most severe, e.g. `CRITICAL 0, HIGH 2, MEDIUM 5, ...`.
+pub fn format_finding_counts(summary: Option<&ImageScanFindingsSummary>) -> String {
+  SEVERITIES
+    .iter()
+    .map(|(_, finding_severity)| {
+      format!(
+        "{} {}",
+        finding_severity.as_str(),
+        finding_count(summary, finding_severity)
+      )
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Checks the scan status of the image.
+///
+/// Basic scanning reports `COMPLETE`, enhanced scanning reports `ACTIVE` once findings are available.
+/// The status is missing until a scan is started.
+pub fn scan_progress(
+  image: &str,
+  image_detail: &ImageDetail,
+) -> miette::Result<ScanProgress, EcsHelperVarietyError> {
+  let Some(scan_status) = image_detail.image_scan_status() else {
+    return Ok(ScanProgress::InProgress);
+  };
+
+  match scan_status.status() {
+    Some(ScanStatus::Complete | ScanStatus::Active) => Ok(ScanProgress::Completed),
+    None | Some(ScanStatus::InProgress | ScanStatus::Pending) => Ok(ScanProgress::InProgress),
+    Some(status) => Err(EcsHelperVarietyError::ImageScanFailed {
+      image: image.to_string(),
+      reason: format!(
+        "{}: {}",
+        status.as_str(),
+        scan_status.description().unwrap_or_default()
+      ),
+    }),
+  }
+}
+
+/// Waits until the image scan is completed, prints the finding counts and checks them with the gate.
+pub async fn wait_for_image_scan(
+  ecr_client: &EcrClient,
+  scan_gate: &ScanGate,
+  repository_name: &str,
+  image_id: ImageIdentifier,
+  image: &str,
+) -> miette::Result<(), EcsHelperVarietyError> {
+  let mut timeout = scan_gate.timeout;
+  let mut is_scan_requested = false;
+
+  while timeout > 0 {
+    let image_detail = ecr_client
+      .describe_images(repository_name, image_id.clone())
+      .await?;
+
+    // the image has not been scanned on push, e.g. scan on push is disabled for the repository
+    if image_detail.image_scan_status().is_none() && !is_scan_requested {
+      log::info!("Starting the scan of {image}");
+      ecr_client
+        .start_image_scan(repository_name, image_id.clone())
+        .await?;
+      is_scan_requested = true;
+    }
+
+    if scan_progress(image, &image_detail)? == ScanProgress::Completed {
+      let summary = image_detail.image_scan_findings_summary();
+
+      println!(
+        "Scan findings of {image}: {}",
+        format_finding_counts(summary)
+      );
+
+      return scan_gate.check(image, summary);
+    }
+
+    log::info!("Waiting for the scan of {image}");
+
+    timeout = timeout.saturating_sub(DEFAULT_STEP);
+    tokio::time::sleep(Duration::from_secs(DEFAULT_STEP)).await;
+  }
+
+  Err(EcsHelperVarietyError::ImageScanTimeout(scan_gate.timeout))
+}
+
+/// Waits for the scans of the image, every platform image of a manifest list is checked.
+///
+/// ECR doesn't scan manifest lists, and buildx pushes an OCI index even for a single platform
+/// because of the provenance attestations.
+pub async fn wait_for_image_scans(
+  ecr_client: &EcrClient,
+  scan_gate: &ScanGate,
+  repository_name: &str,
+  image_id: ImageIdentifier,
+  image: &str,
+) -> miette::Result<(), EcsHelperVarietyError> {
+  let manifest = ecr_client
+    .get_image_manifest(repository_name, image_id.clone())
+    .await?;
+  let platform_images = manifest_list::platform_images(&manifest)?;
+
+  if platform_images.is_empty() {
+    return wait_for_image_scan(ecr_client, scan_gate, repository_name, image_id, image).await;
+  }
+
+  for platform_image in platform_images {
+    wait_for_image_scan(
+      ecr_client,
+      scan_gate,
+      repository_name,
+      ImageIdentifier::builder()
+        .image_digest(&platform_image.digest)
+        .build(),
+      &format!("{image} ({})", platform_image.platform),
+    )
+    .await?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aws_config::{BehaviorVersion, Region, SdkConfig};
+  use aws_sdk_ecr::{
+    config::{Credentials, SharedCredentialsProvider},
+    types::ImageScanStatus,
+  };
+  use aws_smithy_runtime::client::http::test_util::{ReplayEvent, StaticReplayClient};
+  use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+  use aws_smithy_types::body::SdkBody;
+
+  const IMAGE: &str = "123456789012.dkr.ecr.us-east-1.amazonaws.com/shop:1.1";
+
+  fn scan_gate(severity: ScanSeverity, max_findings: i32) -> ScanGate {
+    ScanGate {
+      severity,
+      max_findings,
+      timeout: 60,
+    }
+  }
+
+  fn summary() -> ImageScanFindingsSummary {
+    ImageScanFindingsSummary::builder()
+      .finding_severity_counts(FindingSeverity::High, 2)
+      .finding_severity_counts(FindingSeverity::Medium, 5)
+      .build()
+  }
+
+  fn image_detail(status: Option<ScanStatus>) -> ImageDetail {
+    ImageDetail::builder()
+      .image_scan_status(
+        ImageScanStatus::builder()
+          .set_status(status)
+          .description("UnsupportedImageError: The operating system is not supported")
+          .build(),
+      )
+      .build()
+  }
+
+  #[test]
+  fn test_format_finding_counts() {
+    assert_eq!(
+      format_finding_counts(Some(&summary())),
+      "CRITICAL 0, HIGH 2, MEDIUM 5, LOW 0, INFORMATIONAL 0, UNDEFINED 0"
+    );
+  }
+
+  #[test]
+  fn test_check_scan_gate() {
+    let summary = summary();
+
+    assert!(
+      scan_gate(ScanSeverity::Critical, 0)
+        .check(IMAGE, Some(&summary))
+        .is_ok()
+    );
+    assert!(
+      scan_gate(ScanSeverity::High, 2)
+        .check(IMAGE, Some(&summary))
+        .is_ok()
+    );
+    assert!(scan_gate(ScanSeverity::Low, 0).check(IMAGE, None).is_ok());
+
+    assert!(matches!(
+      scan_gate(ScanSeverity::High, 1).check(IMAGE, Some(&summary)),
+      Err(EcsHelperVarietyError::VulnerableImage { findings, .. })
+        if findings.starts_with("CRITICAL 0, HIGH 2")
+    ));
+    assert!(matches!(
+      scan_gate(ScanSeverity::Medium, 6).check(IMAGE, Some(&summary)),
+      Err(EcsHelperVarietyError::VulnerableImage { .. })
+    ));
+  }
+
+  #[test]
+  fn test_scan_progress() {
+    assert_eq!(
+      scan_progress(IMAGE, &ImageDetail::builder().build()).unwrap(),
+      ScanProgress::InProgress
+    );
+    assert_eq!(
+      scan_progress(IMAGE, &image_detail(Some(ScanStatus::InProgress))).unwrap(),
+      ScanProgress::InProgress
+    );
+    assert_eq!(
+      scan_progress(IMAGE, &image_detail(Some(ScanStatus::Complete))).unwrap(),
+      ScanProgress::Completed
+    );
+    assert_eq!(
+      scan_progress(IMAGE, &image_detail(Some(ScanStatus::Active))).unwrap(),
+      ScanProgress::Completed
+    );
+    assert!(matches!(
+      scan_progress(IMAGE, &image_detail(Some(ScanStatus::UnsupportedImage))),
+      Err(EcsHelperVarietyError::ImageScanFailed { reason, .. })
+        if reason.starts_with("UNSUPPORTED_IMAGE")
+    ));
+  }
+
+  fn replay_event(body: &str) -> ReplayEvent {
+    let request = HttpRequest::new(SdkBody::from(""));
+    let response = http::Response::builder()
+      .status(200)
+      .body(SdkBody::from(body.to_string()))
+      .unwrap();
+
+    ReplayEvent::new(request, response)
+  }
+
+  fn ecr_client(http_client: StaticReplayClient) -> EcrClient {
+    let sdk_config = SdkConfig::builder()
+      .region(Region::new("us-east-1"))
+      .behavior_version(BehaviorVersion::latest())
+      .credentials_provider(SharedCredentialsProvider::new(
+        Credentials::for_tests_with_session_token(),
+      ))
+      .http_client(http_client)
+      .build();
+
+    EcrClient::new(&sdk_config)
+  }
+
+  #[tokio::test]
+  async fn test_wait_for_image_scans_of_single_platform_index() {
+    let index = serde_json::json!({
+      "schemaVersion": 2,
+      "mediaType": "application/vnd.oci.image.index.v1+json",
+      "manifests": [
+        {
+          "digest": "sha256:amd64",
+          "platform": { "os": "linux", "architecture": "amd64" }
+        },
+        {
+          "digest": "sha256:attestation",
+          "platform": { "os": "unknown", "architecture": "unknown" }
+        }
+      ]
+    });
+    let batch_get_image = serde_json::json!({
+      "images": [{
+        "imageId": { "imageTag": "1.1" },
+        "imageManifest": index.to_string(),
+        "imageManifestMediaType": "application/vnd.oci.image.index.v1+json"
+      }]
+    });
+    let http_client = StaticReplayClient::new(vec![
+      replay_event(&batch_get_image.to_string()),
+      replay_event(r#"{"imageDetails": [{"imageScanStatus": {"status": "COMPLETE"}}]}"#),
+    ]);
+
+    wait_for_image_scans(
+      &ecr_client(http_client.clone()),
+      &scan_gate(ScanSeverity::High, 0),
+      "shop",
+      ImageIdentifier::builder().image_tag("1.1").build(),
+      IMAGE,
+    )
+    .await
+    .unwrap();
+
+    let requests = http_client.actual_requests().collect::<Vec<_>>();
+    assert_eq!(requests.len(), 2);
+    let describe_images: serde_json::Value =
+      serde_json::from_slice(requests[1].body().bytes().unwrap()).unwrap();
+    assert_eq!(
+      describe_images["imageIds"][0]["imageDigest"],
+      "sha256:amd64"
+    );
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn test_wait_for_image_scan_starts_missing_scan() {
+    let http_client = StaticReplayClient::new(vec![
+      replay_event(r#"{"imageDetails": [{"imageTags": ["1.1"]}]}"#),
+      replay_event(r#"{"imageScanStatus": {"status": "IN_PROGRESS"}}"#),
+      replay_event(
+        r#"{
+          "imageDetails": [{
+            "imageTags": ["1.1"],
+            "imageScanStatus": {"status": "COMPLETE"},
+            "imageScanFindingsSummary": {"findingSeverityCounts": {"HIGH": 2}}
+          }]
+        }"#,
+      ),
+    ]);
+    let ecr_client = ecr_client(http_client.clone());
+
+    let result = wait_for_image_scan(
+      &ecr_client,
+      &scan_gate(ScanSeverity::High, 1),
+      "shop",
+      ImageIdentifier::builder().image_tag("1.1").build(),
+      IMAGE,
+    )
+    .await;
+
+    assert!(matches!(
+      result,
+      Err(EcsHelperVarietyError::VulnerableImage { .. })
+    ));
+
+    let targets = http_client
+      .actual_requests()
+      .map(|request| request.headers().get("x-amz-target").unwrap().to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      targets,
+      [
+        "AmazonEC2ContainerRegistry_V20150921.DescribeImages",
+        "AmazonEC2ContainerRegistry_V20150921.StartImageScan",
+        "AmazonEC2ContainerRegistry_V20150921.DescribeImages",
+      ]
+    );
+  }
+}
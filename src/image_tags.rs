@@ -0,0 +1,221 @@
+use regex::{Captures, Regex};
+
+use crate::errors::EcsHelperVarietyError;
+
+const SHORT_SHA_LENGTH: usize = 7;
+const MAX_TAG_LENGTH: usize = 128;
+/// Placeholders which may change between the build and the deploy of the same commit.
+const UNSTABLE_PLACEHOLDERS: [&str; 2] = ["branch", "date"];
+
+/// Values of the tag template placeholders.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagContext {
+  pub environment: String,
+  pub commit_sha: String,
+  pub branch: Option<String>,
+  /// Version of the release tag pointing to the commit, without the `v` prefix.
+  pub semver: Option<String>,
+  /// Build date as `YYYYMMDD`.
+  pub date: String,
+}
+
+impl TagContext {
+  fn value(&self, placeholder: &str) -> Result<&str, String> {
+    match placeholder {
+      "environment" => Ok(&self.environment),
+      "sha" => Ok(&self.commit_sha),
+      "short_sha" => Ok(
+        self
+          .commit_sha
+          .get(..SHORT_SHA_LENGTH)
+          .unwrap_or(&self.commit_sha),
+      ),
+      "branch" => self
+        .branch
+        .as_deref()
+        .ok_or_else(|| "branch is not known".to_string()),
+      "semver" => self
+        .semver
+        .as_deref()
+        .ok_or_else(|| "commit has no semver tag".to_string()),
+      "date" => Ok(&self.date),
+      placeholder => Err(format!("unknown placeholder {{{placeholder}}}")),
+    }
+  }
+}
+
+/// Renders the tag template, e.g. `{environment}-{short_sha}`, into a valid image tag.
+///
+/// Characters which are not allowed in image tags, e.g. `/` of `feature/login` branch, are replaced with `-`.
+pub fn render(
+  template: &str,
+  context: &TagContext,
+) -> miette::Result<String, EcsHelperVarietyError> {
+  let placeholder = Regex::new(r"\{([a-z_]+)\}").expect("placeholder regex is valid");
+  let mut error = None;
+
+  let tag = placeholder.replace_all(template, |captures: &Captures| {
+    context
+      .value(&captures[1])
+      .unwrap_or_else(|reason| {
+        error.get_or_insert(reason);
+        ""
+      })
+      .to_string()
+  });
+
+  if let Some(reason) = error {
+    return Err(invalid_template(template, reason));
+  }
+
+  let tag = sanitize(&tag);
+
+  if tag.is_empty() {
+    return Err(invalid_template(template, "tag is empty".to_string()));
+  }
+
+  Ok(tag)
+}
+
+/// Renders the version tag template, which must resolve to the same tag in every job of the commit.
+pub fn render_version(
+  template: &str,
+  context: &TagContext,
+) -> miette::Result<String, EcsHelperVarietyError> {
+  let placeholder = Regex::new(r"\{([a-z_]+)\}").expect("placeholder regex is valid");
+
+  if let Some(captures) = placeholder
+    .captures_iter(template)
+    .find(|captures| UNSTABLE_PLACEHOLDERS.contains(&&captures[1]))
+  {
+    return Err(invalid_template(
+      template,
+      format!(
+        "{{{}}} is not allowed in the version tag, use it in --tag templates",
+        &captures[1]
+      ),
+    ));
+  }
+
+  render(template, context)
+}
+
+fn invalid_template(template: &str, reason: String) -> EcsHelperVarietyError {
+  EcsHelperVarietyError::InvalidTagTemplate {
+    template: template.to_string(),
+    reason,
+  }
+}
+
+/// Image tags may contain letters, digits, `_`, `.` and `-`, must not start with `.` or `-`
+/// and are limited to 128 characters.
+fn sanitize(tag: &str) -> String {
+  tag
+    .chars()
+    .map(|char| {
+      if char.is_ascii_alphanumeric() || matches!(char, '_' | '.' | '-') {
+        char
+      } else {
+        '-'
+      }
+    })
+    .skip_while(|char| matches!(char, '.' | '-'))
+    .take(MAX_TAG_LENGTH)
+    .collect()
+}
+
+/// Parses a release tag, e.g. `v1.2.3` or `1.2.3-rc.1`, into a semver version.
+pub fn parse_semver(tag: &str) -> Option<String> {
+  let semver = Regex::new(r"^v?(\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?)$")
+    .expect("semver regex is valid");
+
+  semver.captures(tag).map(|captures| captures[1].to_string())
+}
+
+/// Formats the date of the timestamp as `YYYYMMDD`.
+pub fn format_date(date_time: aws_smithy_types::DateTime) -> String {
+  date_time
+    .fmt(aws_smithy_types::date_time::Format::DateTime)
+    .map(|date_time| {
+      date_time
+        .chars()
+        .take(10)
+        .filter(|char| *char != '-')
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn context() -> TagContext {
+    TagContext {
+      environment: "production".to_string(),
+      commit_sha: "3f2a1bc9d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3".to_string(),
+      branch: Some("feature/login".to_string()),
+      semver: Some("1.2.3+build.5".to_string()),
+      date: "20261018".to_string(),
+    }
+  }
+
+  #[test]
+  fn test_render() {
+    let context = context();
+
+    assert_eq!(
+      render("{environment}-{short_sha}", &context).unwrap(),
+      "production-3f2a1bc"
+    );
+    assert_eq!(
+      render("{sha}", &context).unwrap(),
+      "3f2a1bc9d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3"
+    );
+    assert_eq!(
+      render("{branch}-{date}", &context).unwrap(),
+      "feature-login-20261018"
+    );
+    assert_eq!(render("{semver}", &context).unwrap(), "1.2.3-build.5");
+    assert_eq!(
+      render("{environment}-latest", &context).unwrap(),
+      "production-latest"
+    );
+  }
+
+  #[test]
+  fn test_render_invalid_template() {
+    let context = TagContext {
+      semver: None,
+      ..context()
+    };
+
+    assert!(matches!(
+      render("{version}", &context),
+      Err(EcsHelperVarietyError::InvalidTagTemplate { reason, .. }) if reason == "unknown placeholder {version}"
+    ));
+    assert!(matches!(
+      render("release-{semver}", &context),
+      Err(EcsHelperVarietyError::InvalidTagTemplate { reason, .. }) if reason == "commit has no semver tag"
+    ));
+    assert!(matches!(
+      render("--", &context),
+      Err(EcsHelperVarietyError::InvalidTagTemplate { .. })
+    ));
+  }
+
+  #[test]
+  fn test_parse_semver() {
+    assert_eq!(parse_semver("v1.2.3"), Some("1.2.3".to_string()));
+    assert_eq!(parse_semver("1.2.3-rc.1"), Some("1.2.3-rc.1".to_string()));
+    assert_eq!(parse_semver("release-1.2"), None);
+  }
+
+  #[test]
+  fn test_format_date() {
+    assert_eq!(
+      format_date(aws_smithy_types::DateTime::from_secs(1_792_281_600)),
+      "20261018"
+    );
+  }
+}
@@ -1,15 +1,29 @@
 pub mod args;
+pub mod branch_mapping;
+pub mod buildx;
 pub mod command;
 pub mod config;
+pub mod config_file;
+pub mod container_engine;
+pub mod docker_config;
+pub mod dockerfile;
 pub mod errors;
+pub mod image_overrides;
+pub mod image_scan;
+pub mod image_tags;
+pub mod manifest_list;
 
 pub mod auth;
+pub mod cloudwatch_logs;
 pub mod ecr;
+pub mod ecr_public;
 pub mod ecs;
 pub mod ssm;
 
 pub mod cluster_helpers;
+pub mod deployment_helpers;
 pub mod service_helpers;
+pub mod task_definition_diff;
 pub mod task_helpers;
 
 pub use command::Command;
@@ -1,12 +1,16 @@
 use clap::Parser;
-use commands::{BuildAndPushCommand, DeployCommand, ExportEnvSecretsCommand};
+use commands::{
+  BuildAndPushCommand, CredentialHelperCommand, DeployCommand, ExportEnvSecretsCommand,
+};
 use ecs_helpers::{
   args::{CommandArguments, Commands},
   command::run_command,
   config::Config,
 };
 
-use crate::commands::{ExecCommand, ExportImagesCommand, LoginCommand, RunCommandCommand};
+use crate::commands::{
+  ExecCommand, ExportImagesCommand, LoginCommand, LogsCommand, RunCommandCommand,
+};
 
 mod commands;
 
@@ -17,10 +21,18 @@ async fn main() -> miette::Result<()> {
   let args = CommandArguments::parse();
   log::debug!("Run with arguments: {:?}", args);
 
+  // docker runs the credential helper outside of the project, it needs only AWS credentials
+  if let Commands::CredentialHelper(options) = &args.cmd {
+    let sdk_config = Config::load_aws_config(&args).await?;
+    let credential_helper_command = CredentialHelperCommand::new(sdk_config, options.clone());
+    return run_command(credential_helper_command).await;
+  }
+
   let config = Config::new(&args).await?;
   log::debug!("Config: {:?}", config);
 
   match args.cmd {
+    Commands::CredentialHelper(_) => unreachable!("credential helper is run without config"),
     Commands::Login(options) => {
       let login_command = LoginCommand::new(config, options);
       run_command(login_command).await
@@ -37,6 +49,10 @@ async fn main() -> miette::Result<()> {
       let exec_command = ExecCommand::new(config, options);
       run_command(exec_command).await
     }
+    Commands::Logs(options) => {
+      let logs_command = LogsCommand::new(config, options);
+      run_command(logs_command).await
+    }
     Commands::ExportEnvSecrets(options) => {
       let export_env_secrets_command = ExportEnvSecretsCommand::new(config, options);
       run_command(export_env_secrets_command).await
@@ -0,0 +1,136 @@
+use serde::Deserialize;
+
+use crate::errors::EcsHelperVarietyError;
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+  #[serde(default)]
+  manifests: Vec<ManifestDescriptor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestDescriptor {
+  digest: String,
+  platform: Option<Platform>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Platform {
+  os: String,
+  architecture: String,
+  variant: Option<String>,
+}
+
+impl std::fmt::Display for Platform {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}/{}", self.os, self.architecture)?;
+
+    if let Some(variant) = &self.variant {
+      write!(f, "/{variant}")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Image of a single platform referenced by the manifest list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlatformImage {
+  pub platform: String,
+  pub digest: String,
+}
+
+/// Returns tag of the single platform image which is joined into the manifest list,
+/// e.g. `1.0-linux-arm64` for `linux/arm64`.
+pub fn platform_tag(version: &str, platform: &str) -> String {
+  format!("{version}-{}", platform.replace('/', "-"))
+}
+
+/// Returns platform images of the manifest list, a single platform manifest has none.
+///
+/// Attestations which buildx stores next to the images have `unknown/unknown` platform and are skipped.
+pub fn platform_images(
+  manifest: &str,
+) -> miette::Result<Vec<PlatformImage>, EcsHelperVarietyError> {
+  let manifest: Manifest = serde_json::from_str(manifest)?;
+
+  Ok(
+    manifest
+      .manifests
+      .into_iter()
+      .filter_map(|descriptor| {
+        let platform = descriptor.platform?;
+
+        (platform.os != "unknown").then(|| PlatformImage {
+          platform: platform.to_string(),
+          digest: descriptor.digest,
+        })
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_platform_tag() {
+    assert_eq!(platform_tag("1.0", "linux/amd64"), "1.0-linux-amd64");
+    assert_eq!(platform_tag("1.0", "linux/arm64/v8"), "1.0-linux-arm64-v8");
+  }
+
+  #[test]
+  fn test_platform_images() {
+    let manifest = r#"{
+      "schemaVersion": 2,
+      "mediaType": "application/vnd.oci.image.index.v1+json",
+      "manifests": [
+        {
+          "mediaType": "application/vnd.oci.image.manifest.v1+json",
+          "digest": "sha256:amd64",
+          "size": 1000,
+          "platform": { "architecture": "amd64", "os": "linux" }
+        },
+        {
+          "mediaType": "application/vnd.oci.image.manifest.v1+json",
+          "digest": "sha256:arm64",
+          "size": 1000,
+          "platform": { "architecture": "arm64", "os": "linux", "variant": "v8" }
+        },
+        {
+          "mediaType": "application/vnd.oci.image.manifest.v1+json",
+          "digest": "sha256:attestation",
+          "size": 800,
+          "platform": { "architecture": "unknown", "os": "unknown" }
+        }
+      ]
+    }"#;
+
+    assert_eq!(
+      platform_images(manifest).unwrap(),
+      vec![
+        PlatformImage {
+          platform: "linux/amd64".to_string(),
+          digest: "sha256:amd64".to_string(),
+        },
+        PlatformImage {
+          platform: "linux/arm64/v8".to_string(),
+          digest: "sha256:arm64".to_string(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_platform_images_of_single_manifest() {
+    let manifest = r#"{
+      "schemaVersion": 2,
+      "mediaType": "application/vnd.docker.distribution.manifest.v2+json",
+      "config": { "digest": "sha256:config", "size": 100 },
+      "layers": []
+    }"#;
+
+    assert_eq!(platform_images(manifest).unwrap(), vec![]);
+  }
+}
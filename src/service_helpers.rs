@@ -1,4 +1,52 @@
-use crate::{config::Config, ecs::EcsClient, errors::EcsHelperVarietyError};
+use crate::{branch_mapping, config::Config, ecs::EcsClient, errors::EcsHelperVarietyError};
+
+/// Service name is the last segment of the service ARN.
+pub fn service_name(service_arn: &str) -> &str {
+  service_arn.rsplit('/').next().unwrap_or(service_arn)
+}
+
+fn filter_services_by_pattern(
+  services: &[String],
+  pattern: &str,
+) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
+  let regex = branch_mapping::pattern_regex(pattern)
+    .map_err(|err| EcsHelperVarietyError::InvalidServicePattern(format!("{pattern}: {err}")))?;
+
+  Ok(
+    services
+      .iter()
+      .filter(|service| regex.is_match(service_name(service)))
+      .cloned()
+      .collect(),
+  )
+}
+
+pub async fn get_services_by_pattern(
+  ecs_client: &EcsClient,
+  config: &Config,
+  cluster: &String,
+  pattern: &str,
+) -> miette::Result<Vec<String>, EcsHelperVarietyError> {
+  let Config {
+    project,
+    application,
+    environment,
+    ..
+  } = config;
+
+  let services = ecs_client.get_services(cluster).await?;
+  let matched_services = filter_services_by_pattern(&services, pattern)?;
+
+  if matched_services.is_empty() {
+    let service_names = services.join(", ");
+
+    return Err(EcsHelperVarietyError::NoSpecifiedService(format!(
+      "No services match the pattern, services you have: {service_names}.\nProject: {project}, application: {application}, environment: {environment}, cluster: {cluster}, pattern: {pattern}"
+    )));
+  }
+
+  Ok(matched_services)
+}
 
 pub async fn get_current_service(
   ecs_client: &EcsClient,
@@ -35,3 +83,45 @@ pub async fn get_current_service(
 
   Ok(service_name.to_owned())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_service_name() {
+    assert_eq!(
+      service_name(
+        "arn:aws:ecs:us-east-1:123456789012:service/shop-production/shop-web-production"
+      ),
+      "shop-web-production"
+    );
+    assert_eq!(service_name("shop-web-production"), "shop-web-production");
+  }
+
+  #[test]
+  fn test_filter_services_by_pattern() {
+    let services = [
+      "arn:aws:ecs:us-east-1:123456789012:service/shop-production/shop-web-production",
+      "arn:aws:ecs:us-east-1:123456789012:service/shop-production/shop-worker-production",
+      "arn:aws:ecs:us-east-1:123456789012:service/shop-production/shop-scheduler-production",
+      "arn:aws:ecs:us-east-1:123456789012:service/shop-production/admin-web-production",
+    ]
+    .map(str::to_string);
+
+    let matched = filter_services_by_pattern(&services, "regex:shop-(web|worker)-.*").unwrap();
+    assert_eq!(matched, services[..2].to_vec());
+
+    let matched = filter_services_by_pattern(&services, "shop-*-production").unwrap();
+    assert_eq!(matched, services[..3].to_vec());
+
+    // the whole service name is matched, cluster name in the ARN is not
+    let matched = filter_services_by_pattern(&services, "shop-production*").unwrap();
+    assert!(matched.is_empty());
+
+    assert!(matches!(
+      filter_services_by_pattern(&services, "regex:shop-(web"),
+      Err(EcsHelperVarietyError::InvalidServicePattern(_))
+    ));
+  }
+}
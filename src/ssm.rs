@@ -31,7 +31,7 @@ impl SSMClient {
     let response = request
       .send()
       .await
-      .map_err(EcsHelperVarietyError::GetSSMParametersError)?;
+      .map_err(|error| EcsHelperVarietyError::GetSSMParametersError(Box::new(error)))?;
 
     let parameters = response.parameters().to_vec();
 
@@ -48,7 +48,7 @@ impl SSMClient {
       .session_id(session_id)
       .send()
       .await
-      .map_err(EcsHelperVarietyError::TerminateSessionError)?;
+      .map_err(|error| EcsHelperVarietyError::TerminateSessionError(Box::new(error)))?;
 
     Ok(())
   }
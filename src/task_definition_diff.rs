@@ -0,0 +1,339 @@
+use std::{collections::BTreeMap, fmt};
+
+use aws_sdk_ecs::types::ContainerDefinition;
+
+const NONE: &str = "(none)";
+
+/// Single changed field of a container definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+  pub field: String,
+  pub current: Option<String>,
+  pub proposed: Option<String>,
+}
+
+impl fmt::Display for FieldChange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}: {} -> {}",
+      self.field,
+      self.current.as_deref().unwrap_or(NONE),
+      self.proposed.as_deref().unwrap_or(NONE)
+    )
+  }
+}
+
+/// Changes of the container definition with the same name in the current and proposed task definitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerDiff {
+  pub name: String,
+  pub changes: Vec<FieldChange>,
+}
+
+impl fmt::Display for ContainerDiff {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.changes.is_empty() {
+      return write!(f, "Container {}: no changes", self.name);
+    }
+
+    write!(f, "Container {}:", self.name)?;
+
+    for change in &self.changes {
+      write!(f, "\n  {change}")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Changes of a single service printed by `deploy --dry-run`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeploymentPlan {
+  pub cluster_arn: String,
+  pub service_arn: String,
+  pub task_definition_arn: String,
+  pub container_diffs: Vec<ContainerDiff>,
+  /// Containers with images from other registries, they are deployed unchanged.
+  pub kept_containers: Vec<String>,
+  /// Containers whose new image can't be deployed, e.g. its tag is not pushed yet.
+  pub missing_images: Vec<(String, String)>,
+}
+
+impl fmt::Display for DeploymentPlan {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Cluster: {}", self.cluster_arn)?;
+    write!(f, "\nService: {}", self.service_arn)?;
+    write!(f, "\nCurrent task definition: {}", self.task_definition_arn)?;
+
+    for container_diff in &self.container_diffs {
+      write!(f, "\n{container_diff}")?;
+    }
+
+    for name in &self.kept_containers {
+      write!(f, "\nContainer {name}: kept, image is not stored in ECR")?;
+    }
+
+    for (name, reason) in &self.missing_images {
+      write!(f, "\nContainer {name}: missing image, {reason}")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Compares image, environment, secrets, cpu and memory of the containers.
+///
+/// Containers are matched by name, containers missing in `proposed` are not compared.
+pub fn diff_container_definitions(
+  current: &[ContainerDefinition],
+  proposed: &[ContainerDefinition],
+) -> Vec<ContainerDiff> {
+  current
+    .iter()
+    .filter_map(|current_container| {
+      let name = current_container.name()?;
+      let proposed_container = proposed
+        .iter()
+        .find(|container| container.name() == Some(name))?;
+
+      Some(ContainerDiff {
+        name: name.to_string(),
+        changes: diff_container_definition(current_container, proposed_container),
+      })
+    })
+    .collect()
+}
+
+fn diff_container_definition(
+  current: &ContainerDefinition,
+  proposed: &ContainerDefinition,
+) -> Vec<FieldChange> {
+  let mut changes = vec![];
+
+  push_change(
+    &mut changes,
+    "image",
+    current.image().map(str::to_string),
+    proposed.image().map(str::to_string),
+  );
+  push_change(
+    &mut changes,
+    "cpu",
+    Some(current.cpu().to_string()),
+    Some(proposed.cpu().to_string()),
+  );
+  push_change(
+    &mut changes,
+    "memory",
+    current.memory().map(|memory| memory.to_string()),
+    proposed.memory().map(|memory| memory.to_string()),
+  );
+  push_change(
+    &mut changes,
+    "memory reservation",
+    current
+      .memory_reservation()
+      .map(|memory_reservation| memory_reservation.to_string()),
+    proposed
+      .memory_reservation()
+      .map(|memory_reservation| memory_reservation.to_string()),
+  );
+
+  diff_named_values(
+    &mut changes,
+    "env",
+    environment_of(current),
+    environment_of(proposed),
+  );
+  diff_named_values(
+    &mut changes,
+    "secret",
+    secrets_of(current),
+    secrets_of(proposed),
+  );
+
+  changes
+}
+
+fn push_change(
+  changes: &mut Vec<FieldChange>,
+  field: &str,
+  current: Option<String>,
+  proposed: Option<String>,
+) {
+  if current != proposed {
+    changes.push(FieldChange {
+      field: field.to_string(),
+      current,
+      proposed,
+    });
+  }
+}
+
+fn diff_named_values(
+  changes: &mut Vec<FieldChange>,
+  kind: &str,
+  current: BTreeMap<String, String>,
+  proposed: BTreeMap<String, String>,
+) {
+  let mut names = current.keys().chain(proposed.keys()).collect::<Vec<_>>();
+  names.sort();
+  names.dedup();
+
+  for name in names {
+    push_change(
+      changes,
+      &format!("{kind} {name}"),
+      current.get(name).cloned(),
+      proposed.get(name).cloned(),
+    );
+  }
+}
+
+fn environment_of(container_definition: &ContainerDefinition) -> BTreeMap<String, String> {
+  container_definition
+    .environment()
+    .iter()
+    .filter_map(|variable| {
+      Some((
+        variable.name()?.to_string(),
+        variable.value().unwrap_or_default().to_string(),
+      ))
+    })
+    .collect()
+}
+
+fn secrets_of(container_definition: &ContainerDefinition) -> BTreeMap<String, String> {
+  container_definition
+    .secrets()
+    .iter()
+    .map(|secret| (secret.name().to_string(), secret.value_from().to_string()))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aws_sdk_ecs::types::{KeyValuePair, Secret};
+
+  fn container(name: &str, image: &str) -> ContainerDefinition {
+    ContainerDefinition::builder()
+      .name(name)
+      .image(image)
+      .cpu(256)
+      .memory(512)
+      .environment(
+        KeyValuePair::builder()
+          .name("RAILS_ENV")
+          .value("production")
+          .build(),
+      )
+      .secrets(
+        Secret::builder()
+          .name("DATABASE_URL")
+          .value_from("arn:aws:ssm:us-east-1:123456789012:parameter/database-url")
+          .build()
+          .unwrap(),
+      )
+      .build()
+  }
+
+  #[test]
+  fn test_diff_without_changes() {
+    let current = vec![container(
+      "web",
+      "123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.0",
+    )];
+
+    let diff = diff_container_definitions(&current, &current);
+
+    assert_eq!(diff.len(), 1);
+    assert!(diff[0].changes.is_empty());
+    assert_eq!(diff[0].to_string(), "Container web: no changes");
+  }
+
+  #[test]
+  fn test_diff_container_definitions() {
+    let current = vec![
+      container(
+        "web",
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.0",
+      ),
+      container("nginx", "nginx:latest"),
+    ];
+
+    let mut web = container(
+      "web",
+      "123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.1",
+    );
+    web.cpu = 512;
+    web.environment = Some(vec![
+      KeyValuePair::builder()
+        .name("RAILS_ENV")
+        .value("staging")
+        .build(),
+      KeyValuePair::builder()
+        .name("WEB_CONCURRENCY")
+        .value("2")
+        .build(),
+    ]);
+    web.secrets = None;
+    let proposed = vec![web];
+
+    let diff = diff_container_definitions(&current, &proposed);
+
+    assert_eq!(diff.len(), 1);
+    assert_eq!(
+      diff[0].to_string(),
+      "Container web:\n  \
+       image: 123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.0 -> 123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.1\n  \
+       cpu: 256 -> 512\n  \
+       env RAILS_ENV: production -> staging\n  \
+       env WEB_CONCURRENCY: (none) -> 2\n  \
+       secret DATABASE_URL: arn:aws:ssm:us-east-1:123456789012:parameter/database-url -> (none)"
+    );
+  }
+
+  #[test]
+  fn test_display_deployment_plan() {
+    let current = vec![
+      container(
+        "web",
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.0",
+      ),
+      container("nginx", "nginx:latest"),
+      container(
+        "worker",
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com/worker:1.0",
+      ),
+    ];
+    let proposed = vec![container(
+      "web",
+      "123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.1",
+    )];
+
+    let plan = DeploymentPlan {
+      cluster_arn: "arn:aws:ecs:us-east-1:123456789012:cluster/production".to_string(),
+      service_arn: "arn:aws:ecs:us-east-1:123456789012:service/production/web".to_string(),
+      task_definition_arn: "arn:aws:ecs:us-east-1:123456789012:task-definition/web:7".to_string(),
+      container_diffs: diff_container_definitions(&current, &proposed),
+      kept_containers: vec!["nginx".to_string()],
+      missing_images: vec![(
+        "worker".to_string(),
+        "Image 1.1 not found in ECR repository worker".to_string(),
+      )],
+    };
+
+    assert_eq!(
+      plan.to_string(),
+      "Cluster: arn:aws:ecs:us-east-1:123456789012:cluster/production\n\
+       Service: arn:aws:ecs:us-east-1:123456789012:service/production/web\n\
+       Current task definition: arn:aws:ecs:us-east-1:123456789012:task-definition/web:7\n\
+       Container web:\n  \
+       image: 123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.0 -> 123456789012.dkr.ecr.us-east-1.amazonaws.com/web:1.1\n\
+       Container nginx: kept, image is not stored in ECR\n\
+       Container worker: missing image, Image 1.1 not found in ECR repository worker"
+    );
+  }
+}
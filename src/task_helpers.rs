@@ -1,3 +1,5 @@
+use aws_sdk_ecs::types::ContainerDefinition;
+
 use crate::{config::Config, ecs::EcsClient, errors::EcsHelperVarietyError};
 
 pub async fn get_current_task(
@@ -81,3 +83,95 @@ pub async fn get_target_container(
 
   Ok(container.name.as_ref().unwrap().to_owned())
 }
+
+/// Builds the container of a one-off task which runs `command` with `bash -c`.
+///
+/// The container is renamed to `container-name` when the name is set, and its logs are written
+/// under the `prefix-name` stream prefix, so they don't mix with the logs of the service.
+pub fn build_custom_container_definition(
+  from_container_definition: &ContainerDefinition,
+  name: Option<&str>,
+  command: &str,
+) -> ContainerDefinition {
+  let mut new_container_definition = from_container_definition.clone();
+
+  let container_name = from_container_definition.name().unwrap();
+  let name = match name {
+    Some(name) => format!("{container_name}-{name}"),
+    None => container_name.to_string(),
+  };
+
+  let mut new_log_configuration = new_container_definition
+    .log_configuration()
+    .unwrap()
+    .to_owned();
+  let mut new_options = new_log_configuration.options().unwrap().to_owned();
+  let new_log_configuration_prefix = format!(
+    "{}-{name}",
+    new_log_configuration
+      .options()
+      .unwrap()
+      .get("awslogs-stream-prefix")
+      .unwrap()
+  );
+  new_options.insert(
+    "awslogs-stream-prefix".to_string(),
+    new_log_configuration_prefix,
+  );
+
+  new_log_configuration.options = Some(new_options);
+
+  new_container_definition.log_configuration = Some(new_log_configuration);
+  new_container_definition.name = Some(name);
+  new_container_definition.command = Some(vec![
+    "bash".to_string(),
+    "-c".to_string(),
+    command.to_string(),
+  ]);
+
+  new_container_definition
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use aws_sdk_ecs::types::{LogConfiguration, LogDriver};
+
+  #[test]
+  fn test_build_custom_container_definition() {
+    let container_definition = ContainerDefinition::builder()
+      .name("web")
+      .log_configuration(
+        LogConfiguration::builder()
+          .log_driver(LogDriver::Awslogs)
+          .options("awslogs-group", "/ecs/shop")
+          .options("awslogs-stream-prefix", "shop")
+          .build()
+          .unwrap(),
+      )
+      .build();
+
+    let new_container_definition =
+      build_custom_container_definition(&container_definition, Some("migrate"), "rails db:migrate");
+
+    assert_eq!(new_container_definition.name(), Some("web-migrate"));
+    assert_eq!(
+      new_container_definition.command(),
+      ["bash", "-c", "rails db:migrate"]
+    );
+
+    let options = new_container_definition
+      .log_configuration()
+      .unwrap()
+      .options()
+      .unwrap();
+    assert_eq!(
+      options.get("awslogs-stream-prefix").map(String::as_str),
+      Some("shop-web-migrate")
+    );
+    assert_eq!(
+      options.get("awslogs-group").map(String::as_str),
+      Some("/ecs/shop")
+    );
+  }
+}
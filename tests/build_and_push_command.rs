@@ -50,3 +50,187 @@ fn test_build_and_push_command_with_target() {
     .arg("test")
     .assert();
 }
+
+#[test]
+fn test_build_and_push_command_public_with_scan_severity() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--public")
+    .arg("--scan-severity")
+    .arg("high")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_build_and_push_command_with_buildx_backend() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--backend")
+    .arg("buildx")
+    .arg("--cache")
+    .arg("--cache-mode")
+    .arg("inline")
+    .assert();
+}
+
+#[test]
+fn test_build_and_push_command_with_unknown_backend() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--backend")
+    .arg("kaniko")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_build_and_push_command_with_multiple_platforms() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--platform")
+    .arg("linux/amd64,linux/arm64")
+    .assert();
+}
+
+#[test]
+fn test_build_and_push_command_with_buildx_backend_and_podman() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--backend")
+    .arg("buildx")
+    .arg("--engine")
+    .arg("podman")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_build_and_push_command_with_tags() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--tag")
+    .arg("{branch}-{date},{short_sha}")
+    .arg("--latest-tag")
+    .arg("{environment}-latest")
+    .assert();
+}
+
+#[test]
+fn test_build_and_push_command_with_latest_tag_and_no_latest() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--no-latest")
+    .arg("--latest-tag")
+    .arg("{environment}-latest")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_build_and_push_command_with_force() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--force")
+    .assert();
+}
+
+#[test]
+fn test_build_and_push_command_with_force_and_retag_existing() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--force")
+    .arg("--retag-existing")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_build_and_push_command_public_with_retag_existing() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("build_and_push")
+    .arg("--image")
+    .arg("test")
+    .arg("--public")
+    .arg("--retag-existing")
+    .assert()
+    .failure();
+}
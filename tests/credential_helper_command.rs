@@ -0,0 +1,40 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+
+#[test]
+fn test_credential_helper_store() {
+  cargo_bin_cmd!("ecs_helpers")
+    .env("AWS_REGION", "us-east-1")
+    .arg("credential-helper")
+    .arg("store")
+    .write_stdin(r#"{"ServerURL":"123456789012.dkr.ecr.us-east-1.amazonaws.com","Username":"AWS","Secret":"token"}"#)
+    .assert()
+    .success()
+    .stdout("");
+}
+
+#[test]
+fn test_credential_helper_get_for_other_registry() {
+  let assert = cargo_bin_cmd!("ecs_helpers")
+    .env("AWS_REGION", "us-east-1")
+    .arg("credential-helper")
+    .arg("get")
+    .write_stdin("ghcr.io")
+    .assert()
+    .code(1);
+
+  let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+  assert!(stderr.contains("Registry ghcr.io is not an ECR registry"));
+}
+
+#[test]
+fn test_credential_helper_without_region() {
+  cargo_bin_cmd!("ecs_helpers")
+    .env_remove("AWS_REGION")
+    .env_remove("AWS_DEFAULT_REGION")
+    .env("AWS_CONFIG_FILE", "/nonexistent")
+    .arg("credential-helper")
+    .arg("get")
+    .write_stdin("123456789012.dkr.ecr.us-east-1.amazonaws.com")
+    .assert()
+    .failure();
+}
@@ -19,3 +19,108 @@ fn test_deploy_command_without_environment() {
     .assert()
     .failure();
 }
+
+#[test]
+fn test_deploy_command_with_rollback() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--environment")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("deploy")
+    .arg("--timeout")
+    .arg("1")
+    .arg("--cluster")
+    .arg("test")
+    .arg("--service")
+    .arg("test")
+    .arg("--rollback")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_deploy_command_with_multiple_services() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--environment")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("deploy")
+    .arg("--timeout")
+    .arg("1")
+    .arg("--cluster")
+    .arg("test")
+    .arg("--service")
+    .arg("web,worker")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_deploy_command_with_service_and_pattern() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--environment")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("deploy")
+    .arg("--service")
+    .arg("web")
+    .arg("--service-pattern")
+    .arg("^test-")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_deploy_command_with_image_overrides() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--environment")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("deploy")
+    .arg("--timeout")
+    .arg("1")
+    .arg("--cluster")
+    .arg("test")
+    .arg("--service")
+    .arg("test")
+    .arg("--image-override")
+    .arg("web=3f2a1bc,nginx=nginx:1.27")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_deploy_command_with_scan_severity() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--environment")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("deploy")
+    .arg("--timeout")
+    .arg("1")
+    .arg("--cluster")
+    .arg("test")
+    .arg("--service")
+    .arg("test")
+    .arg("--scan-severity")
+    .arg("HIGH")
+    .arg("--scan-max-findings")
+    .arg("2")
+    .assert()
+    .failure();
+}
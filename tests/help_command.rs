@@ -78,3 +78,23 @@ fn test_deploy_help() {
     .success()
     .stderr("");
 }
+
+#[test]
+fn test_logs_help() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("logs")
+    .arg("--help")
+    .assert()
+    .success()
+    .stderr("");
+}
+
+#[test]
+fn test_credential_helper_help() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("credential-helper")
+    .arg("--help")
+    .assert()
+    .success()
+    .stderr("");
+}
@@ -12,3 +12,33 @@ fn test_ecr_login_command_without_environment() {
     .arg("ecr_login")
     .assert();
 }
+
+#[test]
+fn test_ecr_login_command_with_invalid_registry() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--aws-account-id")
+    .arg("123456789012")
+    .arg("login")
+    .arg("--registry")
+    .arg("tooling")
+    .assert()
+    .failure();
+}
+
+#[test]
+fn test_ecr_login_command_with_external_id_without_role_arn() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--external-id")
+    .arg("shop")
+    .arg("login")
+    .assert()
+    .failure();
+}
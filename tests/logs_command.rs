@@ -0,0 +1,21 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+
+#[test]
+fn test_logs_command_without_environment() {
+  cargo_bin_cmd!("ecs_helpers")
+    .arg("--project")
+    .arg("test")
+    .arg("--application")
+    .arg("test")
+    .arg("--environment")
+    .arg("test")
+    .arg("logs")
+    .arg("--cluster")
+    .arg("test")
+    .arg("--service")
+    .arg("test")
+    .arg("--since")
+    .arg("1h")
+    .assert()
+    .failure();
+}